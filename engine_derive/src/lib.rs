@@ -0,0 +1,105 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Ident, LitStr};
+
+/// Implements `engine_lib::core::events::event::EventMarker`
+///
+/// ```ignore
+/// #[derive(Debug, Event)]
+/// #[event(name = "player died", priority = blocking)]
+/// struct PlayerDied;
+/// ```
+///
+/// `name` defaults to `std::any::type_name`, like a hand-written impl, `priority` is `queued` or
+/// `blocking` and defaults to `queued`, each can be given once
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let mut name: Option<LitStr> = None;
+    let mut priority: Option<Ident> = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("event"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                if name.is_some() {
+                    return Err(meta.error("`name` is given more than once"));
+                }
+                name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("priority") {
+                if priority.is_some() {
+                    return Err(meta.error("`priority` is given more than once"));
+                }
+                let value: Ident = meta.value()?.parse()?;
+                priority = Some(match value.to_string().as_str() {
+                    "queued" => Ident::new("Queued", value.span()),
+                    "blocking" => Ident::new("Blocking", value.span()),
+                    _ => return Err(meta.error("expected `queued` or `blocking`")),
+                });
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `priority`"))
+            }
+        })?;
+    }
+
+    let priority = priority.unwrap_or_else(|| Ident::new("Queued", ident.span()));
+    // without a name the trait's default is kept, so derived and hand-written events match
+    let name = name.map(|name| {
+        quote! {
+            fn name() -> &'static str {
+                #name
+            }
+        }
+    });
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::engine_lib::core::events::event::EventMarker for #ident #ty_generics
+            #where_clause
+        {
+            #name
+            fn default_priority() -> ::engine_lib::core::events::EventPriority {
+                ::engine_lib::core::events::EventPriority::#priority
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_attribute_test() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[event(name = "a", name = "b")]
+            struct Repeated;
+        };
+        let error = expand(input).unwrap_err();
+        assert!(error.to_string().contains("more than once"));
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[event(priority = blocking)]
+            #[event(priority = queued)]
+            struct Repeated;
+        };
+        assert!(expand(input).is_err());
+
+        let input: DeriveInput = syn::parse_quote! {
+            struct Unnamed;
+        };
+        assert!(!expand(input).unwrap().to_string().contains("fn name"));
+    }
+}
@@ -1,5 +1,7 @@
 use std::ops;
 
+use crate::core::error::EngineError;
+
 /// represents a generic matrix
 /// I is rows, J is columns
 #[derive(Clone)]
@@ -18,7 +20,7 @@ impl<const I: usize> Matrix<I, I> {
         for i in 0..I {
             let _ = matrix.set((i, i), 1.0);
         }
-        return matrix;
+        matrix
     }
 }
 
@@ -29,13 +31,16 @@ impl<const I: usize, const J: usize> Matrix<I, J> {
     pub fn get(&self, ij: (usize, usize)) -> Option<f32> {
         self.values.get(ij.0)?.get(ij.1).copied()
     }
-    pub const fn set(&mut self, ij: (usize, usize), value: f32) -> Result<(), ()> {
+    pub const fn set(&mut self, ij: (usize, usize), value: f32) -> Result<(), EngineError> {
         if ij.0 >= I || ij.1 >= J {
-            return Err(());
+            return Err(EngineError::MatrixIndexOutOfBounds {
+                index: ij,
+                size: (I, J),
+            });
         }
 
         self.values[ij.0][ij.1] = value;
-        return Ok(());
+        Ok(())
     }
 
     pub fn matrix_multiply<const J2: usize>(&self, rhs: Matrix<J, J2>) -> Matrix<I, J2> {
@@ -49,7 +54,7 @@ impl<const I: usize, const J: usize> Matrix<I, J> {
                 let _ = m.set((i, j), total);
             }
         }
-        return m;
+        m
     }
 
     pub fn scalar_multiply(mut self, rhs: f32) -> Self {
@@ -59,7 +64,7 @@ impl<const I: usize, const J: usize> Matrix<I, J> {
             }
         }
 
-        return self;
+        self
     }
 }
 
@@ -73,7 +78,7 @@ impl<const I: usize, const J: usize> ops::Add for Matrix<I, J> {
             }
         }
 
-        return self;
+        self
     }
 }
 impl<const I: usize, const J: usize> ops::Sub for Matrix<I, J> {
@@ -86,7 +91,7 @@ impl<const I: usize, const J: usize> ops::Sub for Matrix<I, J> {
             }
         }
 
-        return self;
+        self
     }
 }
 impl<const I: usize, const J: usize, const J2: usize> ops::Mul<Matrix<J, J2>> for Matrix<I, J> {
@@ -118,7 +123,11 @@ mod tests {
     fn set() {
         let mut my_matrix = Matrix::new([[1.0, 5.0], [4.0, 2.0]]);
 
-        let _ = my_matrix.set((1, 0), 10.0).unwrap();
+        my_matrix.set((1, 0), 10.0).unwrap();
+        assert!(matches!(
+            my_matrix.set((2, 0), 10.0),
+            Err(EngineError::MatrixIndexOutOfBounds { .. })
+        ));
 
         let other = Matrix::new([[1.0, 5.0], [10.0, 2.0]]);
 
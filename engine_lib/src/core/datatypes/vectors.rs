@@ -10,6 +10,9 @@ impl Vector2 {
     pub const fn new(x: f32, y: f32) -> Vector2 {
         Vector2 { x, y }
     }
+}
+#[allow(dead_code)]
+impl Vector2 {
     const ZERO: Vector2 = Vector2::new(0.0, 0.0);
     const UP: Vector2 = Vector2::new(0.0, 1.0);
     const DOWN: Vector2 = Vector2::new(0.0, -1.0);
@@ -19,7 +22,7 @@ impl Vector2 {
 
 impl Vector2 {
     pub fn magnitude(&self) -> f32 {
-        ((self.x * self.x + self.y * self.y) as f32).sqrt()
+        (self.x * self.x + self.y * self.y).sqrt()
     }
     pub fn normalized(self) -> Vector2 {
         let magnitude = self.magnitude();
@@ -88,6 +91,9 @@ impl Vector3 {
     pub const fn new(x: f32, y: f32, z: f32) -> Vector3 {
         Vector3 { x, y, z }
     }
+}
+#[allow(dead_code)]
+impl Vector3 {
     const ZERO: Vector3 = Vector3::new(0.0, 0.0, 0.0);
     const UP: Vector3 = Vector3::new(0.0, 1.0, 0.0);
     const DOWN: Vector3 = Vector3::new(0.0, -1.0, 0.0);
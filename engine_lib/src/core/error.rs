@@ -0,0 +1,125 @@
+use std::fmt;
+
+use ash::vk;
+
+/// Everything that can go wrong while building or running an app
+#[derive(Debug)]
+pub enum EngineError {
+    /// the winit event loop couldn't be created or exited with an error
+    EventLoop(winit::error::EventLoopError),
+    WindowCreation(winit::error::OsError),
+    /// the window or display handle needed to create a surface isn't available
+    WindowHandle(winit::raw_window_handle::HandleError),
+    /// a vulkan call failed, e.g. while initializing the renderer
+    Vulkan(vk::Result),
+    /// no physical device matches the [`crate::renderer::RendererConfig`], the message lists the
+    /// candidates
+    NoSuitableDevice(String),
+    NoSuitableMemoryType,
+    /// a mesh without triangles can't be uploaded
+    EmptyMesh,
+    /// the SPIR-V of a shader couldn't be read
+    Shader(std::io::Error),
+    Image(image::ImageError),
+    InvalidIcon(winit::window::BadIcon),
+    /// the offscreen image couldn't be read back, see [`crate::renderer::Renderer::read_back`]
+    ReadBack(&'static str),
+    /// a name passed to vulkan contains a nul byte
+    InvalidName(std::ffi::NulError),
+    /// a recording file couldn't be read or written
+    Recording(std::io::Error),
+    /// a recording file couldn't be parsed, `line` starts at 1
+    InvalidRecording {
+        line: usize,
+        message: String,
+    },
+    /// an init hook added with [`crate::core::initialization::AppBuilder::add_init_hook`] failed
+    InitHook(String),
+    MatrixIndexOutOfBounds {
+        index: (usize, usize),
+        size: (usize, usize),
+    },
+}
+
+impl EngineError {
+    pub fn init_hook(message: impl Into<String>) -> Self {
+        EngineError::InitHook(message.into())
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::EventLoop(e) => write!(f, "event loop error: {}", e),
+            EngineError::WindowCreation(e) => write!(f, "failed to create window: {}", e),
+            EngineError::WindowHandle(e) => write!(f, "window handle unavailable: {}", e),
+            EngineError::Vulkan(e) => write!(f, "vulkan error: {}", e),
+            EngineError::NoSuitableDevice(e) => write!(f, "no suitable device: {}", e),
+            EngineError::NoSuitableMemoryType => write!(f, "no suitable memory type"),
+            EngineError::EmptyMesh => write!(f, "cannot upload a mesh without triangles"),
+            EngineError::Shader(e) => write!(f, "failed to read shader: {}", e),
+            EngineError::Image(e) => write!(f, "image error: {}", e),
+            EngineError::InvalidIcon(e) => write!(f, "invalid icon: {}", e),
+            EngineError::ReadBack(message) => write!(f, "failed to read back: {}", message),
+            EngineError::InvalidName(e) => write!(f, "invalid name: {}", e),
+            EngineError::Recording(e) => write!(f, "failed to access recording: {}", e),
+            EngineError::InvalidRecording { line, message } => {
+                write!(f, "invalid recording at line {}: {}", line, message)
+            }
+            EngineError::InitHook(message) => write!(f, "init hook failed: {}", message),
+            EngineError::MatrixIndexOutOfBounds { index, size } => write!(
+                f,
+                "index {:?} is out of bounds for a {}x{} matrix",
+                index, size.0, size.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::EventLoop(e) => Some(e),
+            EngineError::WindowCreation(e) => Some(e),
+            EngineError::WindowHandle(e) => Some(e),
+            EngineError::Vulkan(e) => Some(e),
+            EngineError::Shader(e) => Some(e),
+            EngineError::Image(e) => Some(e),
+            EngineError::InvalidIcon(e) => Some(e),
+            EngineError::InvalidName(e) => Some(e),
+            EngineError::Recording(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<winit::error::EventLoopError> for EngineError {
+    fn from(e: winit::error::EventLoopError) -> Self {
+        EngineError::EventLoop(e)
+    }
+}
+impl From<winit::error::OsError> for EngineError {
+    fn from(e: winit::error::OsError) -> Self {
+        EngineError::WindowCreation(e)
+    }
+}
+impl From<winit::raw_window_handle::HandleError> for EngineError {
+    fn from(e: winit::raw_window_handle::HandleError) -> Self {
+        EngineError::WindowHandle(e)
+    }
+}
+impl From<vk::Result> for EngineError {
+    fn from(e: vk::Result) -> Self {
+        EngineError::Vulkan(e)
+    }
+}
+impl From<image::ImageError> for EngineError {
+    fn from(e: image::ImageError) -> Self {
+        EngineError::Image(e)
+    }
+}
+impl From<std::ffi::NulError> for EngineError {
+    fn from(e: std::ffi::NulError) -> Self {
+        EngineError::InvalidName(e)
+    }
+}
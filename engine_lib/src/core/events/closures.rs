@@ -0,0 +1,200 @@
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use super::event::EventMarker;
+use super::{
+    listener_from_func, EventEvaluateState, EventListener, EventSystem, ListenerHandle,
+    ListenerOptions,
+};
+
+/// lets closure listeners return nothing, which leaves the event unhandled
+impl From<()> for EventEvaluateState {
+    fn from(_: ()) -> Self {
+        EventEvaluateState::Unhandled
+    }
+}
+
+type OnceFn<T> = Box<dyn FnOnce(&T) -> EventEvaluateState>;
+
+/// Calls a closure for the first event it gets, then removes itself
+struct OnceListener<T: EventMarker + 'static> {
+    listener: Option<OnceFn<T>>,
+    /// set once the listener was added
+    handle: Rc<Cell<Option<ListenerHandle>>>,
+    removed: Arc<Mutex<Vec<ListenerHandle>>>,
+}
+impl<T: EventMarker + 'static> EventListener<T> for OnceListener<T> {
+    fn invoke_event(&mut self, event: &T) -> EventEvaluateState {
+        match self.listener.take() {
+            Some(f) => {
+                if let Some(handle) = self.handle.get() {
+                    self.removed.lock().unwrap().push(handle);
+                }
+                (f)(event)
+            }
+            None => EventEvaluateState::Unhandled,
+        }
+    }
+}
+
+struct NextEventState<T> {
+    event: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Resolves with the next event of type `T` that reaches the listeners, get one with
+/// [`EventSystem::once`]
+///
+/// can be awaited, or checked every frame with [`NextEvent::take`], events handled or cancelled by
+/// a layer never reach it, see [`crate::core::layers::Layer::on_event`]
+pub struct NextEvent<T> {
+    state: Rc<RefCell<NextEventState<T>>>,
+}
+impl<T> NextEvent<T> {
+    pub fn is_ready(&self) -> bool {
+        self.state.borrow().event.is_some()
+    }
+    /// the event once it arrived, `None` before and after it was taken
+    pub fn take(&self) -> Option<T> {
+        self.state.borrow_mut().event.take()
+    }
+}
+impl<T> Future for NextEvent<T> {
+    type Output = T;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.borrow_mut();
+        match state.event.take() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl EventSystem {
+    /// adds a closure listener, the closure can return nothing or an [`EventEvaluateState`]
+    ///
+    /// ```ignore
+    /// app.event_system.on(|event: &WindowResize| println!("resized to {:?}", event.0));
+    /// ```
+    pub fn on<T, R, F>(&mut self, mut f: F) -> ListenerHandle
+    where
+        T: EventMarker + 'static,
+        R: Into<EventEvaluateState>,
+        F: FnMut(&T) -> R + 'static,
+    {
+        self.add_listener(Box::new(listener_from_func(move |event: &T| {
+            f(event).into()
+        })))
+    }
+    /// adds a closure listener that is removed after its first call
+    pub fn on_once<T, R, F>(&mut self, f: F) -> ListenerHandle
+    where
+        T: EventMarker + 'static,
+        R: Into<EventEvaluateState>,
+        F: FnOnce(&T) -> R + 'static,
+    {
+        self.add_once_listener(f, ListenerOptions::default())
+    }
+    fn add_once_listener<T, R, F>(&mut self, f: F, options: ListenerOptions) -> ListenerHandle
+    where
+        T: EventMarker + 'static,
+        R: Into<EventEvaluateState>,
+        F: FnOnce(&T) -> R + 'static,
+    {
+        let handle = Rc::new(Cell::new(None));
+        let listener = OnceListener {
+            listener: Some(Box::new(move |event: &T| f(event).into())),
+            handle: handle.clone(),
+            removed: self.removed.clone(),
+        };
+        let added = self.add_listener_with(Box::new(listener), options);
+        handle.set(Some(added));
+        added
+    }
+    /// resolves with a copy of the next event of type `T`, it is seen before the other listeners
+    /// and doesn't stop them from getting the event
+    ///
+    /// layers get events before the listeners, while a layer handles or cancels every `T` the
+    /// future doesn't resolve and its listener stays registered
+    pub fn once<T: EventMarker + Clone + 'static>(&mut self) -> NextEvent<T> {
+        let state = Rc::new(RefCell::new(NextEventState {
+            event: None,
+            waker: None,
+        }));
+        let next = state.clone();
+        self.add_once_listener(
+            move |event: &T| {
+                let mut next = next.borrow_mut();
+                next.event = Some(event.clone());
+                if let Some(waker) = next.waker.take() {
+                    waker.wake();
+                }
+            },
+            ListenerOptions::default()
+                .with_priority(i32::MAX)
+                .with_name("once"),
+        );
+        NextEvent { state }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::event::{WindowClose, WindowResize};
+    use super::super::EventInfo;
+    use super::*;
+
+    #[test]
+    fn closure_listener_test() {
+        let mut event_system = EventSystem::new();
+        let sizes = Rc::new(RefCell::new(vec![]));
+        let resized = sizes.clone();
+        let mut count = 0;
+        event_system.on(move |event: &WindowResize| {
+            count += 1;
+            resized.borrow_mut().push((count, event.0));
+        });
+        let closes = Rc::new(RefCell::new(0));
+        let closed = closes.clone();
+        event_system.on_once(move |_: &WindowClose| *closed.borrow_mut() += 1);
+
+        for size in [(1, 1), (2, 2)] {
+            event_system.execute(EventInfo::blocking(WindowResize(size)));
+            event_system.execute(EventInfo::blocking(WindowClose));
+        }
+        assert_eq!(*sizes.borrow(), vec![(1, (1, 1)), (2, (2, 2))]);
+        assert_eq!(*closes.borrow(), 1);
+        assert_eq!(
+            event_system
+                .stats_of::<WindowClose>()
+                .unwrap()
+                .listeners_invoked,
+            1
+        );
+    }
+
+    #[test]
+    fn next_event_test() {
+        let mut event_system = EventSystem::new();
+        event_system.on(|_: &WindowResize| EventEvaluateState::Handled);
+        let mut next = event_system.once::<WindowResize>();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert!(Pin::new(&mut next).poll(&mut cx).is_pending());
+        event_system.execute(EventInfo::blocking(WindowResize((3, 4))));
+        event_system.execute(EventInfo::blocking(WindowResize((5, 6))));
+        assert!(next.is_ready());
+        match Pin::new(&mut next).poll(&mut cx) {
+            Poll::Ready(event) => assert_eq!(event.0, (3, 4)),
+            Poll::Pending => panic!("the event didn't arrive"),
+        }
+        assert!(next.take().is_none());
+    }
+}
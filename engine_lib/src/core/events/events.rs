@@ -1,18 +1,31 @@
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Instant;
 
 use super::keyboard::{KeyState, Keycode};
 use super::mouse::MouseButton;
+use super::{EventStats, HandledBy};
+use engine_derive::Event;
 
 pub struct EventInfo<T>
 where
     T: EventMarker + 'static,
 {
-    event: T,
+    pub(crate) event: T,
     priority: EventPriority,
 }
 impl<T: EventMarker + 'static> EventInfo<T> {
+    /// uses the default priority of the event, see [`EventMarker::default_priority`]
+    pub fn new(event: T) -> Self {
+        EventInfo {
+            event,
+            priority: T::default_priority(),
+        }
+    }
     pub const fn queued(event: T) -> Self {
         EventInfo {
             event,
@@ -26,157 +39,596 @@ impl<T: EventMarker + 'static> EventInfo<T> {
         }
     }
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventEvaluateState {
+    /// the event was used, the next listeners don't get it
     Handled,
     Unhandled,
+    /// the event must not take effect, the next listeners don't get it and the caller of
+    /// [`EventSystem::execute`] sees it was cancelled
+    Cancelled,
+}
+
+/// An event of any type, passed to layers by [`crate::core::layers::Layer::on_event`]
+pub struct Event<'a> {
+    event: &'a dyn Any,
+    type_name: &'static str,
+    name: &'static str,
+}
+impl<'a> Event<'a> {
+    pub fn new<E: EventMarker + 'static>(event: &'a E) -> Self {
+        Event {
+            event,
+            type_name: std::any::type_name::<E>(),
+            name: E::name(),
+        }
+    }
+    /// the event when it is an `E`
+    pub fn downcast<E: EventMarker + 'static>(&self) -> Option<&'a E> {
+        self.event.downcast_ref::<E>()
+    }
+    pub fn is<E: EventMarker + 'static>(&self) -> bool {
+        self.event.is::<E>()
+    }
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+    /// see [`EventMarker::name`]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Receives every event before the listeners, returning [`EventEvaluateState::Handled`] stops the
+/// event from reaching the listeners
+///
+/// the app dispatches events to its layers through this
+pub trait EventSink {
+    fn dispatch(&mut self, event: &Event) -> EventEvaluateState;
+}
+/// dispatches to listeners only
+impl EventSink for () {
+    fn dispatch(&mut self, _event: &Event) -> EventEvaluateState {
+        EventEvaluateState::Unhandled
+    }
 }
 
 pub mod event {
     #![allow(unused_variables)]
     use super::*;
 
-    pub trait EventMarker: Any {}
+    /// Implemented by every event, usually with `#[derive(Event)]`
+    pub trait EventMarker: Any {
+        /// a human readable name, used by tracing and recording tools
+        fn name() -> &'static str
+        where
+            Self: Sized,
+        {
+            std::any::type_name::<Self>()
+        }
+        /// the priority used by [`EventInfo::new`]
+        fn default_priority() -> EventPriority
+        where
+            Self: Sized,
+        {
+            EventPriority::Queued
+        }
+    }
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
     pub struct KeyboardEvent(pub Keycode, pub KeyState);
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
     pub struct MouseEvent(pub MouseButton, pub KeyState);
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
     pub struct MouseMotion(pub (f32, f32));
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
     pub struct MouseScroll(pub f32);
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
     pub struct AppUpdate;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
+    #[event(priority = blocking)]
     pub struct AppRender;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
+    #[event(priority = blocking)]
     pub struct WindowFocus;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
+    #[event(priority = blocking)]
     pub struct WindowLoseFocus;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
+    #[event(priority = blocking)]
     pub struct WindowResize(pub (u32, u32));
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Event)]
+    #[event(priority = blocking)]
     pub struct WindowClose;
-
-    impl EventMarker for KeyboardEvent {}
-    impl EventMarker for MouseEvent {}
-    impl EventMarker for MouseMotion {}
-    impl EventMarker for MouseScroll {}
-    impl EventMarker for AppUpdate {}
-    impl EventMarker for AppRender {}
-    impl EventMarker for WindowFocus {}
-    impl EventMarker for WindowLoseFocus {}
-    impl EventMarker for WindowResize {}
-    impl EventMarker for WindowClose {}
 }
 use event::*;
 
-#[derive(Clone)]
-enum EventPriority {
-    /// puts the event in a queue to be processed next frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EventPriority {
+    /// puts the event in a queue to be processed on the next [`EventSystem::update`]
     Queued,
     /// executes the event right away
     Blocking,
 }
 
-pub struct EventSystem {
-    queue: Vec<Box<dyn FnOnce(&mut HashMap<TypeId, Box<dyn Any>>) -> ()>>,
+/// Refers to a listener added with [`EventSystem::add_listener`], dropping it keeps the listener
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerHandle {
+    id: u64,
+    event: TypeId,
+}
+
+/// How a listener is registered, see [`EventSystem::add_listener_with`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListenerOptions {
+    /// listeners with a higher priority get events first, listeners with the same priority get
+    /// them in the order they were added
+    pub priority: i32,
+    /// shown in the logs, for debugging
+    pub name: Option<String>,
+}
+impl ListenerOptions {
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+/// Removes its listener from the event system when dropped, get one with
+/// [`EventSystem::guard`]
+///
+/// e.g. kept by a layer and dropped in [`crate::core::layers::Layer::close`]
+#[must_use = "the listener is removed when the guard is dropped"]
+pub struct ListenerGuard {
+    handle: ListenerHandle,
+    removed: Arc<Mutex<Vec<ListenerHandle>>>,
+}
+impl ListenerGuard {
+    pub fn handle(&self) -> ListenerHandle {
+        self.handle
+    }
+}
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        self.removed.lock().unwrap().push(self.handle);
+    }
+}
 
-    /// Real type of Any: `Vec<Box<EventListener<EventMarker>>>`
-    /// `listeners: HashMap<TypeId, Box<Vec<ConcreteEventListener<EventMarker>>>>`
-    listeners: HashMap<TypeId, Box<dyn Any>>,
+/// The listeners of one event type, lets the event system remove listeners without knowing the
+/// event type
+trait AnyListenerList {
+    fn remove(&mut self, id: u64) -> bool;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<T: EventMarker + 'static> AnyListenerList for Vec<ConcreteEventListener<T>> {
+    fn remove(&mut self, id: u64) -> bool {
+        match self.iter().position(|listener| listener.id == id) {
+            Some(index) => {
+                _ = Vec::remove(self, index);
+                true
+            }
+            None => false,
+        }
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Real type of the lists: `Vec<ConcreteEventListener<EventMarker>>`
+type Listeners = HashMap<TypeId, Box<dyn AnyListenerList>>;
+
+/// What events are executed against
+struct Dispatcher {
+    listeners: Listeners,
+    stats: HashMap<TypeId, EventStats>,
+}
+
+type QueuedEvent = Box<dyn FnOnce(&mut Dispatcher, &mut dyn EventSink)>;
+
+/// The default for [`EventFlush::UntilEmpty`]
+pub const DEFAULT_MAX_FLUSH_CYCLES: u32 = 16;
+
+/// What [`EventSystem::update`] does with events queued while it dispatches events
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EventFlush {
+    /// they are dispatched on the next update, usually the next frame
+    #[default]
+    NextFrame,
+    /// they are dispatched in the same update, until the queue is empty or the queue was drained
+    /// `max_cycles` times, the remaining events are left for the next update, `max_cycles` is at
+    /// least 1
+    UntilEmpty { max_cycles: u32 },
+}
+
+/// Queues events from listeners while the event system dispatches, get one with
+/// [`EventSystem::queue_handle`]
+#[derive(Clone)]
+pub struct EventQueue {
+    queue: Rc<RefCell<VecDeque<QueuedEvent>>>,
+}
+impl EventQueue {
+    pub fn queue_event<T: EventMarker>(&self, event: T) {
+        let event = EventInfo::queued(event);
+        self.queue
+            .borrow_mut()
+            .push_back(Box::new(move |dispatcher, sink| {
+                _ = execute::<T>(dispatcher, sink, event);
+            }));
+    }
+}
+
+type SentEvent = Box<dyn FnOnce(&mut Dispatcher, &mut dyn EventSink) + Send>;
+
+/// Sends events to the event system from any thread, get one with [`EventSystem::sender`]
+///
+/// the events are queued on the next [`EventSystem::update`], after the events already queued
+#[derive(Clone)]
+pub struct EventSender {
+    sender: mpsc::Sender<SentEvent>,
+}
+impl EventSender {
+    /// returns false when the event system was dropped
+    pub fn send<T: EventMarker + Send>(&self, event: T) -> bool {
+        let event = EventInfo::queued(event);
+        self.sender
+            .send(Box::new(move |dispatcher, sink| {
+                _ = execute::<T>(dispatcher, sink, event);
+            }))
+            .is_ok()
+    }
+}
+
+pub struct EventSystem {
+    queue: EventQueue,
+    sender: mpsc::Sender<SentEvent>,
+    received: mpsc::Receiver<SentEvent>,
+    flush: EventFlush,
+    dispatcher: Dispatcher,
+    next_listener_id: u64,
+    /// listeners whose guard was dropped, removed before the next event is executed
+    pub(super) removed: Arc<Mutex<Vec<ListenerHandle>>>,
 }
 
 /// Handles the engine's events
 impl EventSystem {
     pub fn new() -> EventSystem {
+        let (sender, received) = mpsc::channel();
         EventSystem {
-            queue: vec![],
-            listeners: HashMap::new(),
+            sender,
+            received,
+            queue: EventQueue {
+                queue: Rc::new(RefCell::new(VecDeque::new())),
+            },
+            flush: EventFlush::default(),
+            dispatcher: Dispatcher {
+                listeners: HashMap::new(),
+                stats: HashMap::new(),
+            },
+            next_listener_id: 0,
+            removed: Arc::new(Mutex::new(vec![])),
         }
     }
 }
+impl Default for EventSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl EventSystem {
+    pub fn set_flush(&mut self, flush: EventFlush) {
+        self.flush = match flush {
+            // zero cycles would never dispatch anything
+            EventFlush::UntilEmpty { max_cycles } => EventFlush::UntilEmpty {
+                max_cycles: max_cycles.max(1),
+            },
+            EventFlush::NextFrame => flush,
+        };
+    }
+    pub fn flush(&self) -> EventFlush {
+        self.flush
+    }
+    /// what happened to the events of each type since the last [`EventSystem::reset_stats`]
+    pub fn stats(&self) -> impl Iterator<Item = &EventStats> {
+        self.dispatcher.stats.values()
+    }
+    pub fn stats_of<T: EventMarker>(&self) -> Option<&EventStats> {
+        self.dispatcher.stats.get(&TypeId::of::<T>())
+    }
+    /// e.g. every frame, to show per frame stats
+    pub fn reset_stats(&mut self) {
+        self.dispatcher.stats.clear();
+    }
+    /// logs the stats at debug level, the slowest event types first
+    pub fn log_stats(&self) {
+        let mut stats: Vec<&EventStats> = self.stats().collect();
+        stats.sort_by_key(|stats| std::cmp::Reverse(stats.total_time));
+        for stats in stats {
+            crate::core::logging::engine::debug!(
+                "{}: {} dispatched, {} handled, {} listeners invoked, {:?} total, {:?} average",
+                stats.name,
+                stats.dispatched,
+                stats.handled,
+                stats.listeners_invoked,
+                stats.total_time,
+                stats.average_time()
+            );
+        }
+    }
+    /// a handle listeners can keep to queue events while they handle one
+    pub fn queue_handle(&self) -> EventQueue {
+        self.queue.clone()
+    }
+    /// a handle other threads can send events with, e.g. asset loading
+    pub fn sender(&self) -> EventSender {
+        EventSender {
+            sender: self.sender.clone(),
+        }
+    }
+    /// the number of events waiting for the next [`EventSystem::update`], without the events sent
+    /// by an [`EventSender`] since the last update
+    pub fn queued_len(&self) -> usize {
+        self.queue.queue.borrow().len()
+    }
+    /// queues the event, or executes it right away when it is blocking, blocking events only
+    /// reach the listeners, see [`EventSystem::queue_event_with`]
     pub fn queue_event<T: EventMarker>(&mut self, event: EventInfo<T>) {
-        match event.priority.clone() {
-            EventPriority::Queued => self.queue.insert(
-                0,
-                Box::new(move |listeners| execute::<T>(listeners, event.into())),
-            ),
-            EventPriority::Blocking => self.execute::<T>(event.into()),
+        self.queue_event_with(event, &mut ());
+    }
+    /// like [`EventSystem::queue_event`], blocking events are dispatched to `sink` before the
+    /// listeners
+    pub fn queue_event_with<T: EventMarker>(
+        &mut self,
+        event: EventInfo<T>,
+        sink: &mut dyn EventSink,
+    ) {
+        match event.priority {
+            EventPriority::Queued => self.queue.queue_event(event.event),
+            EventPriority::Blocking => _ = self.execute_with::<T>(event, sink),
         }
     }
-    pub fn add_listener<T, E>(&mut self, listener: Box<E>) -> &mut Self
+    /// adds a listener with the default [`ListenerOptions`], the handle can be passed to
+    /// [`EventSystem::remove_listener`] or [`EventSystem::guard`]
+    pub fn add_listener<T, E>(&mut self, listener: Box<E>) -> ListenerHandle
     where
         T: EventMarker + 'static,
         E: EventListener<T> + 'static,
     {
-        let event_type_id = TypeId::of::<T>();
-        match self.listeners.get_mut(&event_type_id) {
-            Some(v) => v
-                .downcast_mut::<Vec<ConcreteEventListener<T>>>()
-                .expect("failed to downcast to listener list")
-                .push(ConcreteEventListener(listener)),
-            None => {
-                _ = self.listeners.insert(
-                    event_type_id,
-                    Box::new(vec![ConcreteEventListener(listener)]),
-                )
-            }
+        self.add_listener_with(listener, ListenerOptions::default())
+    }
+    /// adds a listener with a priority and a name, e.g. so a debug console gets keyboard input
+    /// before gameplay
+    pub fn add_listener_with<T, E>(
+        &mut self,
+        listener: Box<E>,
+        options: ListenerOptions,
+    ) -> ListenerHandle
+    where
+        T: EventMarker + 'static,
+        E: EventListener<T> + 'static,
+    {
+        self.insert_listener(ListenerKind::Ref(listener), options)
+    }
+    /// adds a listener that can change the event before the next listeners get it, e.g. to
+    /// remap a key
+    pub fn add_mut_listener<T, E>(&mut self, listener: Box<E>) -> ListenerHandle
+    where
+        T: EventMarker + 'static,
+        E: MutEventListener<T> + 'static,
+    {
+        self.add_mut_listener_with(listener, ListenerOptions::default())
+    }
+    /// like [`EventSystem::add_mut_listener`], with a priority and a name
+    pub fn add_mut_listener_with<T, E>(
+        &mut self,
+        listener: Box<E>,
+        options: ListenerOptions,
+    ) -> ListenerHandle
+    where
+        T: EventMarker + 'static,
+        E: MutEventListener<T> + 'static,
+    {
+        self.insert_listener(ListenerKind::Mut(listener), options)
+    }
+    /// the handle the next listener added for `T` gets
+    fn insert_listener<T: EventMarker + 'static>(
+        &mut self,
+        listener: ListenerKind<T>,
+        options: ListenerOptions,
+    ) -> ListenerHandle {
+        let handle = ListenerHandle {
+            id: self.next_listener_id,
+            event: TypeId::of::<T>(),
+        };
+        self.next_listener_id += 1;
+        let listener = ConcreteEventListener {
+            id: handle.id,
+            priority: options.priority,
+            name: options.name,
+            listener,
+        };
+        let listeners = self
+            .dispatcher
+            .listeners
+            .entry(handle.event)
+            .or_insert_with(|| Box::new(Vec::<ConcreteEventListener<T>>::new()))
+            .as_any_mut()
+            .downcast_mut::<Vec<ConcreteEventListener<T>>>()
+            .expect("failed to downcast to listener list");
+        // after the listeners with the same priority
+        let index = listeners
+            .iter()
+            .position(|other| other.priority < listener.priority)
+            .unwrap_or(listeners.len());
+        listeners.insert(index, listener);
+        handle
+    }
+    /// returns false when the listener was already removed
+    pub fn remove_listener(&mut self, handle: ListenerHandle) -> bool {
+        self.dispatcher
+            .listeners
+            .get_mut(&handle.event)
+            .is_some_and(|listeners| listeners.remove(handle.id))
+    }
+    /// removes the listener when the returned guard is dropped
+    pub fn guard(&self, handle: ListenerHandle) -> ListenerGuard {
+        ListenerGuard {
+            handle,
+            removed: self.removed.clone(),
+        }
+    }
+    fn remove_guarded(&mut self) {
+        let removed = std::mem::take(&mut *self.removed.lock().unwrap());
+        for handle in removed {
+            self.remove_listener(handle);
         }
-        self
     }
 
-    /// execute a specific event immediately
-    pub fn execute<E: EventMarker + 'static>(&mut self, event: EventInfo<E>) {
-        execute::<E>(&mut self.listeners, event);
+    /// execute a specific event immediately, returns whether it was handled or cancelled
+    ///
+    /// the event only reaches the listeners, see [`EventSystem::execute_with`]
+    pub fn execute<E: EventMarker + 'static>(&mut self, event: EventInfo<E>) -> EventEvaluateState {
+        self.execute_with(event, &mut ())
+    }
+    /// execute a specific event immediately, `sink` gets it before the listeners
+    pub fn execute_with<E: EventMarker + 'static>(
+        &mut self,
+        event: EventInfo<E>,
+        sink: &mut dyn EventSink,
+    ) -> EventEvaluateState {
+        self.remove_guarded();
+        execute::<E>(&mut self.dispatcher, sink, event)
     }
+    /// executes the queued events in the order they were queued, events queued meanwhile are
+    /// handled following [`EventSystem::flush`]
     pub fn update(&mut self) {
-        while let Some(event_handler) = self.queue.pop() {
-            (event_handler)(&mut self.listeners)
+        self.update_with(&mut ());
+    }
+    /// like [`EventSystem::update`], `sink` gets each event before the listeners
+    pub fn update_with(&mut self, sink: &mut dyn EventSink) {
+        self.queue
+            .queue
+            .borrow_mut()
+            .extend(self.received.try_iter().map(|event| event as QueuedEvent));
+        let max_cycles = match self.flush {
+            EventFlush::NextFrame => 1,
+            EventFlush::UntilEmpty { max_cycles } => max_cycles,
+        };
+        for _ in 0..max_cycles {
+            // taken out so listeners can queue events meanwhile
+            let events = std::mem::take(&mut *self.queue.queue.borrow_mut());
+            if events.is_empty() {
+                return;
+            }
+            for event_handler in events {
+                self.remove_guarded();
+                (event_handler)(&mut self.dispatcher, sink)
+            }
+        }
+        if self.flush != EventFlush::NextFrame && self.queued_len() > 0 {
+            crate::core::logging::engine::warning!(
+                "{} events are still queued after {} cycles, they are left for the next update",
+                self.queued_len(),
+                max_cycles
+            );
         }
     }
 }
 
-fn execute<'a, E: EventMarker + 'static>(
-    listeners: &'a mut HashMap<TypeId, Box<dyn Any>>,
+fn execute<E: EventMarker + 'static>(
+    dispatcher: &mut Dispatcher,
+    sink: &mut dyn EventSink,
     event: EventInfo<E>,
-) {
+) -> EventEvaluateState {
+    let start = Instant::now();
+    let mut event = event.event;
+    let (listeners_invoked, state, handled_by) =
+        dispatch(&mut dispatcher.listeners, sink, &mut event);
+    if let Some(handled_by) = &handled_by {
+        crate::core::logging::engine::trace!("{} {:?} by {:?}", E::name(), state, handled_by);
+    }
+    dispatcher
+        .stats
+        .entry(TypeId::of::<E>())
+        .or_insert_with(|| EventStats::new(E::name()))
+        .record(listeners_invoked, state, handled_by, start.elapsed());
+    state
+}
+
+/// returns the number of listeners invoked, the outcome and who handled or cancelled the event
+fn dispatch<E: EventMarker + 'static>(
+    listeners: &mut Listeners,
+    sink: &mut dyn EventSink,
+    event: &mut E,
+) -> (u64, EventEvaluateState, Option<HandledBy>) {
+    let state = sink.dispatch(&Event::new(event));
+    if state != EventEvaluateState::Unhandled {
+        return (0, state, Some(HandledBy::Layer));
+    }
+    let mut invoked = 0;
     for listener in match listeners.get_mut(&TypeId::of::<E>()) {
         Some(i) => i
+            .as_any_mut()
             .downcast_mut::<Vec<ConcreteEventListener<E>>>()
             .expect("failed to downcast to event list"),
-        None => return,
+        None => return (0, EventEvaluateState::Unhandled, None),
     }
     .iter_mut()
     {
-        match listener.0.invoke_event(&event.event) {
-            EventEvaluateState::Handled => {
-                crate::core::logging::engine::trace!("event handled");
-                break;
-            }
-            EventEvaluateState::Unhandled => (),
+        invoked += 1;
+        let state = match &mut listener.listener {
+            ListenerKind::Ref(listener) => listener.invoke_event(event),
+            ListenerKind::Mut(listener) => listener.invoke_event_mut(event),
+        };
+        if state != EventEvaluateState::Unhandled {
+            let handled_by = HandledBy::Listener {
+                handle: ListenerHandle {
+                    id: listener.id,
+                    event: TypeId::of::<E>(),
+                },
+                name: listener.name.clone(),
+            };
+            return (invoked, state, Some(handled_by));
         }
     }
+    (invoked, EventEvaluateState::Unhandled, None)
 }
 
 pub trait EventListener<T: EventMarker + 'static> {
     fn invoke_event(&mut self, event: &T) -> EventEvaluateState;
 }
-struct ConcreteEventListener<T: EventMarker + 'static>(Box<dyn EventListener<T>>);
+/// A listener that can change the event, the next listeners get the changed event, see
+/// [`EventSystem::add_mut_listener`]
+pub trait MutEventListener<T: EventMarker + 'static> {
+    fn invoke_event_mut(&mut self, event: &mut T) -> EventEvaluateState;
+}
+enum ListenerKind<T: EventMarker + 'static> {
+    Ref(Box<dyn EventListener<T>>),
+    Mut(Box<dyn MutEventListener<T>>),
+}
+struct ConcreteEventListener<T: EventMarker + 'static> {
+    id: u64,
+    priority: i32,
+    name: Option<String>,
+    listener: ListenerKind<T>,
+}
 
 pub const fn listener_from_func<F, T>(f: F) -> impl EventListener<T>
 where
-    F: Fn(&T) -> EventEvaluateState + 'static,
+    F: FnMut(&T) -> EventEvaluateState + 'static,
     T: EventMarker + 'static,
 {
     FuncEventListener {
@@ -187,7 +639,7 @@ where
 
 struct FuncEventListener<F, T: EventMarker>
 where
-    F: Fn(&T) -> EventEvaluateState + 'static,
+    F: FnMut(&T) -> EventEvaluateState + 'static,
     T: EventMarker,
 {
     f: F,
@@ -195,7 +647,7 @@ where
 }
 impl<F, T> EventListener<T> for FuncEventListener<F, T>
 where
-    F: Fn(&T) -> EventEvaluateState + 'static,
+    F: FnMut(&T) -> EventEvaluateState + 'static,
     T: EventMarker + 'static,
 {
     fn invoke_event(&mut self, event: &T) -> EventEvaluateState {
@@ -230,6 +682,31 @@ mod tests {
             TypeId::of::<event::AppRender>()
         )
     }
+    #[derive(Event)]
+    #[event(name = "player died", priority = blocking)]
+    struct PlayerDied;
+
+    #[test]
+    fn derived_event_test() {
+        assert_eq!(PlayerDied::name(), "player died");
+        assert_eq!(PlayerDied::default_priority(), EventPriority::Blocking);
+        assert_eq!(
+            event::KeyboardEvent::name(),
+            std::any::type_name::<event::KeyboardEvent>()
+        );
+        assert_eq!(event::AppUpdate::default_priority(), EventPriority::Queued);
+
+        let mut event_system = EventSystem::new();
+        let number = Rc::new(RefCell::new(0));
+        let counter = number.clone();
+        event_system.add_listener(Box::new(listener_from_func(move |_: &PlayerDied| {
+            *counter.borrow_mut() += 1;
+            EventEvaluateState::Handled
+        })));
+
+        event_system.queue_event(EventInfo::new(PlayerDied));
+        assert_eq!(*number.borrow(), 1);
+    }
     #[test]
     fn event_system_test() {
         let mut event_system = EventSystem::new();
@@ -268,4 +745,240 @@ mod tests {
 
         assert_eq!(*number.borrow(), 1);
     }
+    struct OrderListener {
+        order: Rc<RefCell<Vec<u32>>>,
+        number: u32,
+        state: EventEvaluateState,
+    }
+    impl EventListener<event::AppUpdate> for OrderListener {
+        fn invoke_event(&mut self, _event: &event::AppUpdate) -> EventEvaluateState {
+            self.order.borrow_mut().push(self.number);
+            self.state
+        }
+    }
+
+    #[test]
+    fn listener_priority_test() {
+        let mut event_system = EventSystem::new();
+        let order = Rc::new(RefCell::new(vec![]));
+        let listener = |number, state| {
+            Box::new(OrderListener {
+                order: order.clone(),
+                number,
+                state,
+            })
+        };
+
+        event_system.add_listener(listener(0, EventEvaluateState::Unhandled));
+        event_system.add_listener_with(
+            listener(1, EventEvaluateState::Unhandled),
+            ListenerOptions::default().with_priority(10),
+        );
+        event_system.add_listener(listener(2, EventEvaluateState::Unhandled));
+        event_system.add_listener_with(
+            listener(3, EventEvaluateState::Unhandled),
+            ListenerOptions::default()
+                .with_priority(10)
+                .with_name("console"),
+        );
+        event_system.add_listener_with(
+            listener(4, EventEvaluateState::Handled),
+            ListenerOptions::default().with_priority(-1),
+        );
+        event_system.add_listener_with(
+            listener(5, EventEvaluateState::Unhandled),
+            ListenerOptions::default().with_priority(-2),
+        );
+
+        event_system.execute(EventInfo::blocking(event::AppUpdate));
+        assert_eq!(*order.borrow(), vec![1, 3, 0, 2, 4]);
+    }
+    /// queues another update event for the first `remaining` events
+    struct RequeueListener {
+        queue: EventQueue,
+        times_called: Rc<RefCell<u32>>,
+        remaining: u32,
+    }
+    impl EventListener<event::AppUpdate> for RequeueListener {
+        fn invoke_event(&mut self, _event: &event::AppUpdate) -> EventEvaluateState {
+            *self.times_called.borrow_mut() += 1;
+            if self.remaining > 0 {
+                self.remaining -= 1;
+                self.queue.queue_event(event::AppUpdate);
+            }
+            EventEvaluateState::Handled
+        }
+    }
+    fn requeue_system(remaining: u32) -> (EventSystem, Rc<RefCell<u32>>) {
+        let mut event_system = EventSystem::new();
+        let number = Rc::new(RefCell::new(0));
+        event_system.add_listener(Box::new(RequeueListener {
+            queue: event_system.queue_handle(),
+            times_called: number.clone(),
+            remaining,
+        }));
+        (event_system, number)
+    }
+
+    #[test]
+    fn queued_during_dispatch_deferred_test() {
+        let (mut event_system, number) = requeue_system(5);
+
+        event_system.queue_event(EventInfo::queued(event::AppUpdate));
+        event_system.update();
+        assert_eq!(*number.borrow(), 1);
+        assert_eq!(event_system.queued_len(), 1);
+
+        event_system.update();
+        assert_eq!(*number.borrow(), 2);
+    }
+    #[test]
+    fn queued_during_dispatch_flushed_test() {
+        let (mut event_system, number) = requeue_system(5);
+        event_system.set_flush(EventFlush::UntilEmpty { max_cycles: 4 });
+
+        event_system.queue_event(EventInfo::queued(event::AppUpdate));
+        event_system.update();
+        assert_eq!(*number.borrow(), 4);
+        assert_eq!(event_system.queued_len(), 1);
+
+        event_system.update();
+        assert_eq!(*number.borrow(), 6);
+        assert_eq!(event_system.queued_len(), 0);
+    }
+    #[test]
+    fn flush_zero_cycles_test() {
+        let (mut event_system, number) = requeue_system(5);
+        event_system.set_flush(EventFlush::UntilEmpty { max_cycles: 0 });
+        assert_eq!(
+            event_system.flush(),
+            EventFlush::UntilEmpty { max_cycles: 1 }
+        );
+
+        event_system.queue_event(EventInfo::queued(event::AppUpdate));
+        event_system.update();
+        assert_eq!(*number.borrow(), 1);
+    }
+    #[test]
+    fn queue_order_test() {
+        let mut event_system = EventSystem::new();
+        let order = Rc::new(RefCell::new(vec![]));
+        let counter = order.clone();
+        event_system.add_listener(Box::new(listener_from_func(
+            move |event: &event::MouseScroll| {
+                counter.borrow_mut().push(event.0);
+                EventEvaluateState::Unhandled
+            },
+        )));
+
+        for i in 0..3 {
+            event_system.queue_event(EventInfo::queued(event::MouseScroll(i as f32)));
+        }
+        event_system.update();
+        assert_eq!(*order.borrow(), vec![0.0, 1.0, 2.0]);
+    }
+    #[test]
+    fn event_sender_test() {
+        let mut event_system = EventSystem::new();
+        let number = Rc::new(RefCell::new(0));
+        let counter = number.clone();
+        event_system.add_listener(Box::new(listener_from_func(
+            move |event: &event::MouseScroll| {
+                *counter.borrow_mut() += event.0 as u32;
+                EventEvaluateState::Unhandled
+            },
+        )));
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let sender = event_system.sender();
+                std::thread::spawn(move || assert!(sender.send(event::MouseScroll(1.0))))
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*number.borrow(), 0);
+
+        event_system.update();
+        assert_eq!(*number.borrow(), 4);
+
+        let sender = event_system.sender();
+        drop(event_system);
+        assert!(!sender.send(event::MouseScroll(1.0)));
+    }
+    /// clamps the scroll delta
+    struct ClampListener;
+    impl MutEventListener<event::MouseScroll> for ClampListener {
+        fn invoke_event_mut(&mut self, event: &mut event::MouseScroll) -> EventEvaluateState {
+            event.0 = event.0.clamp(-1.0, 1.0);
+            EventEvaluateState::Unhandled
+        }
+    }
+
+    #[test]
+    fn mut_listener_and_cancel_test() {
+        let mut event_system = EventSystem::new();
+        let seen = Rc::new(RefCell::new(vec![]));
+        let scrolls = seen.clone();
+        event_system.add_listener(Box::new(listener_from_func(
+            move |event: &event::MouseScroll| {
+                scrolls.borrow_mut().push(event.0);
+                EventEvaluateState::Handled
+            },
+        )));
+        event_system.add_mut_listener_with(
+            Box::new(ClampListener),
+            ListenerOptions::default().with_priority(1),
+        );
+        event_system.add_listener_with(
+            Box::new(listener_from_func(|event: &event::MouseScroll| {
+                match event.0 == 0.0 {
+                    true => EventEvaluateState::Cancelled,
+                    false => EventEvaluateState::Unhandled,
+                }
+            })),
+            ListenerOptions::default().with_priority(2),
+        );
+
+        assert_eq!(
+            event_system.execute(EventInfo::blocking(event::MouseScroll(5.0))),
+            EventEvaluateState::Handled
+        );
+        assert_eq!(
+            event_system.execute(EventInfo::blocking(event::MouseScroll(0.0))),
+            EventEvaluateState::Cancelled
+        );
+        assert_eq!(
+            event_system.execute(EventInfo::blocking(event::MouseMotion((1.0, 1.0)))),
+            EventEvaluateState::Unhandled
+        );
+        assert_eq!(*seen.borrow(), vec![1.0]);
+
+        let stats = event_system.stats_of::<event::MouseScroll>().unwrap();
+        assert_eq!((stats.handled, stats.cancelled), (1, 1));
+    }
+    #[test]
+    fn remove_listener_test() {
+        let mut event_system = EventSystem::new();
+        let number = Rc::new(RefCell::new(0));
+
+        let handle = event_system.add_listener(Box::new(Listener {
+            times_called: number.clone(),
+        }));
+        let guarded = event_system.add_listener(Box::new(Listener {
+            times_called: number.clone(),
+        }));
+        let guard = event_system.guard(guarded);
+
+        assert!(event_system.remove_listener(handle));
+        assert!(!event_system.remove_listener(handle));
+        event_system.execute(EventInfo::blocking(event::AppUpdate));
+        assert_eq!(*number.borrow(), 1);
+
+        drop(guard);
+        event_system.execute(EventInfo::blocking(event::AppUpdate));
+        assert_eq!(*number.borrow(), 1);
+        assert!(!event_system.remove_listener(guarded));
+    }
 }
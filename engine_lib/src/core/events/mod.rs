@@ -1,4 +1,7 @@
+mod closures;
+#[allow(clippy::module_inception)]
 mod events;
+mod stats;
 
 pub mod keyboard {
     #[derive(Debug, PartialEq, Copy, Clone)]
@@ -11,7 +14,7 @@ pub mod keyboard {
     pub type Keycode = winit::keyboard::KeyCode;
 }
 pub mod mouse {
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, PartialEq, Copy, Clone)]
     pub enum MouseButton {
         Left,
         Right,
@@ -21,4 +24,7 @@ pub mod mouse {
     }
 }
 
+pub use closures::*;
+pub use engine_derive::Event;
 pub use events::*;
+pub use stats::*;
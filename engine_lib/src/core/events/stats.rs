@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use super::{EventEvaluateState, ListenerHandle};
+
+/// What stopped an event from reaching the remaining listeners, by handling or cancelling it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HandledBy {
+    /// a layer, see [`crate::core::layers::Layer::on_event`]
+    Layer,
+    Listener {
+        handle: ListenerHandle,
+        /// see [`super::ListenerOptions::name`]
+        name: Option<String>,
+    },
+}
+
+/// What the event system did with the events of one type, get them with
+/// [`super::EventSystem::stats`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventStats {
+    /// the name of the event type
+    pub name: &'static str,
+    /// the number of events dispatched
+    pub dispatched: u64,
+    /// the number of listener calls, over every event
+    pub listeners_invoked: u64,
+    /// the number of events a layer or listener handled
+    pub handled: u64,
+    /// the number of events a layer or listener cancelled
+    pub cancelled: u64,
+    /// who handled or cancelled the last event, `None` when nothing did
+    pub last_handled_by: Option<HandledBy>,
+    /// the time spent in the layers and listeners, over every event
+    pub total_time: Duration,
+    /// the time the last dispatch took
+    pub last_time: Duration,
+}
+
+impl EventStats {
+    pub fn new(name: &'static str) -> Self {
+        EventStats {
+            name,
+            ..Default::default()
+        }
+    }
+    pub fn average_time(&self) -> Duration {
+        match self.dispatched {
+            0 => Duration::ZERO,
+            // dividing the nanoseconds keeps counts above u32::MAX exact
+            dispatched => {
+                Duration::from_nanos((self.total_time.as_nanos() / dispatched as u128) as u64)
+            }
+        }
+    }
+    pub(crate) fn record(
+        &mut self,
+        listeners_invoked: u64,
+        state: EventEvaluateState,
+        handled_by: Option<HandledBy>,
+        time: Duration,
+    ) {
+        self.dispatched += 1;
+        self.listeners_invoked += listeners_invoked;
+        match state {
+            EventEvaluateState::Handled => self.handled += 1,
+            EventEvaluateState::Cancelled => self.cancelled += 1,
+            EventEvaluateState::Unhandled => (),
+        }
+        self.last_handled_by = handled_by;
+        self.total_time += time;
+        self.last_time = time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    #[test]
+    fn event_stats_test() {
+        let mut event_system = EventSystem::new();
+        let calls = Rc::new(RefCell::new(0));
+        let counter = calls.clone();
+        event_system.add_listener(Box::new(listener_from_func(
+            move |_: &event::MouseScroll| {
+                *counter.borrow_mut() += 1;
+                EventEvaluateState::Unhandled
+            },
+        )));
+        let console = event_system.add_listener_with(
+            Box::new(listener_from_func(|event: &event::MouseScroll| {
+                match event.0 > 0.0 {
+                    true => EventEvaluateState::Handled,
+                    false => EventEvaluateState::Unhandled,
+                }
+            })),
+            ListenerOptions::default()
+                .with_priority(1)
+                .with_name("console"),
+        );
+
+        event_system.execute(EventInfo::blocking(event::MouseScroll(-1.0)));
+        event_system.execute(EventInfo::blocking(event::MouseScroll(1.0)));
+        event_system.execute(EventInfo::blocking(event::AppUpdate));
+
+        let stats = event_system.stats_of::<event::MouseScroll>().unwrap();
+        assert!(stats.name.ends_with("MouseScroll"));
+        assert_eq!(stats.dispatched, 2);
+        assert_eq!(stats.listeners_invoked, 3);
+        assert_eq!(stats.handled, 1);
+        assert_eq!(
+            stats.last_handled_by,
+            Some(HandledBy::Listener {
+                handle: console,
+                name: Some("console".to_string())
+            })
+        );
+        assert_eq!(event_system.stats().count(), 2);
+
+        let many = EventStats {
+            dispatched: 1 << 32,
+            total_time: Duration::from_secs(1 << 32),
+            ..EventStats::new("many")
+        };
+        assert_eq!(many.average_time(), Duration::from_secs(1));
+
+        event_system.reset_stats();
+        assert!(event_system.stats_of::<event::MouseScroll>().is_none());
+    }
+}
@@ -1,57 +1,370 @@
-type InitializeHook = dyn FnOnce(&mut crate::App) -> Result<(), ()>;
+use std::path::{Path, PathBuf};
+
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Fullscreen, Icon, Window, WindowAttributes};
+
+use crate::core::error::EngineError;
+use crate::core::logging::engine::warning;
+use crate::renderer::{PresentMode, RendererConfig};
+use crate::runtime::headless::HeadlessConfig;
+use crate::runtime::pacing::{is_valid_fps, FrameLimit, FramePacer};
+use crate::runtime::recording::{EventRecorder, EventReplay, Recording};
+use crate::runtime::timestep::FixedTimestep;
+
+type InitializeHook = dyn FnOnce(&mut crate::App) -> Result<(), EngineError>;
 
 pub struct AppBuilder {
     init_hooks: Vec<Box<InitializeHook>>,
-    layers: Vec<Box<dyn crate::core::layers::Layer>>,
+    /// the layers and whether they are overlays
+    layers: Vec<(Box<dyn crate::core::layers::Layer>, bool)>,
+    headless: Option<HeadlessConfig>,
+    renderer_config: RendererConfig,
+    window_data: WindowData,
+    icon_path: Option<PathBuf>,
+    recording_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+    fixed_timestep: FixedTimestep,
+    frame_pacer: FramePacer,
 }
 impl AppBuilder {
     pub fn new() -> Self {
         AppBuilder {
             init_hooks: vec![],
             layers: vec![],
+            headless: None,
+            renderer_config: RendererConfig::default(),
+            window_data: WindowData::default(),
+            icon_path: None,
+            recording_path: None,
+            replay_path: None,
+            fixed_timestep: FixedTimestep::default(),
+            frame_pacer: FramePacer::default(),
         }
     }
 }
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl AppBuilder {
     pub fn add_init_hook<T>(mut self, f: T) -> Self
     where
-        T: FnOnce(&mut crate::App) -> Result<(), ()> + 'static,
+        T: FnOnce(&mut crate::App) -> Result<(), EngineError> + 'static,
     {
         self.init_hooks.push(Box::new(f));
         self
     }
     pub fn add_layer(mut self, layer: Box<dyn crate::core::layers::Layer>) -> Self {
-        self.layers.push(layer);
+        self.layers.push((layer, false));
+        self
+    }
+    /// adds a layer above every other layer, see [`crate::App::add_overlay`]
+    pub fn add_overlay(mut self, layer: Box<dyn crate::core::layers::Layer>) -> Self {
+        self.layers.push((layer, true));
+        self
+    }
+    /// runs the app without creating a window or renderer, see [`HeadlessConfig`]
+    pub fn headless(mut self) -> Self {
+        self.headless.get_or_insert_with(HeadlessConfig::new);
+        self
+    }
+    /// stops the app after `ticks` frames, implies [`AppBuilder::headless`]
+    pub fn with_tick_limit(mut self, ticks: u64) -> Self {
+        let config = self.headless.take().unwrap_or_default();
+        self.headless = Some(config.with_tick_limit(ticks));
+        self
+    }
+    /// stops the app once `f` returns true, implies [`AppBuilder::headless`]
+    pub fn with_stop_condition<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&crate::App) -> bool + 'static,
+    {
+        let config = self.headless.take().unwrap_or_default();
+        self.headless = Some(config.with_stop_condition(f));
+        self
+    }
+    /// sets the options the renderer is created with, see [`RendererConfig`]
+    pub fn with_renderer_config(mut self, config: RendererConfig) -> Self {
+        self.renderer_config = config;
+        self
+    }
+    /// sets how many frames the renderer can record ahead of the GPU, defaults to
+    /// [`crate::renderer::DEFAULT_FRAMES_IN_FLIGHT`]
+    pub fn with_frames_in_flight(mut self, count: usize) -> Self {
+        self.renderer_config.frames_in_flight = count;
+        self
+    }
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.window_data.title = title.into();
+        self
+    }
+    /// the inner size of the window in physical pixels
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.window_data.size = (width, height);
         self
     }
-    pub fn build(self) -> Result<crate::App, ()> {
-        let mut app = crate::App::new(WindowData::default());
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.window_data.resizable = resizable;
+        self
+    }
+    pub fn with_fullscreen(mut self, fullscreen: FullscreenMode) -> Self {
+        self.window_data.fullscreen = Some(fullscreen);
+        self
+    }
+    pub fn with_min_size(mut self, width: u32, height: u32) -> Self {
+        self.window_data.min_size = Some((width, height));
+        self
+    }
+    pub fn with_max_size(mut self, width: u32, height: u32) -> Self {
+        self.window_data.max_size = Some((width, height));
+        self
+    }
+    pub fn with_cursor_grab(mut self, grab: CursorGrab) -> Self {
+        self.window_data.cursor_grab = grab;
+        self
+    }
+    pub fn with_cursor_visible(mut self, visible: bool) -> Self {
+        self.window_data.cursor_visible = visible;
+        self
+    }
+    /// loads the window icon from an image file when the app is built
+    pub fn with_icon(mut self, path: impl AsRef<Path>) -> Self {
+        self.icon_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+    /// [`PresentMode::Fifo`] when `vsync` is on, [`PresentMode::Immediate`] otherwise
+    pub fn with_vsync(self, vsync: bool) -> Self {
+        self.with_present_mode(match vsync {
+            true => PresentMode::Fifo,
+            false => PresentMode::Immediate,
+        })
+    }
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.renderer_config.present_mode = present_mode;
+        self
+    }
+    /// runs [`crate::core::layers::Layer::fixed_update`] `rate` times per second, defaults to
+    /// [`crate::runtime::timestep::DEFAULT_FIXED_RATE`]
+    ///
+    /// a rate that isn't a positive number is ignored
+    pub fn with_fixed_timestep(mut self, rate: f64) -> Self {
+        if !FixedTimestep::is_valid_rate(rate) {
+            warning!("ignoring fixed timestep rate {rate}, it must be a positive number");
+            return self;
+        }
+        let max_steps = self.fixed_timestep.max_steps();
+        self.fixed_timestep = FixedTimestep::new(rate).with_max_steps(max_steps);
+        self
+    }
+    /// the most fixed updates run in a single frame, defaults to
+    /// [`crate::runtime::timestep::DEFAULT_MAX_FIXED_STEPS`]
+    pub fn with_max_fixed_steps(mut self, max_steps: u32) -> Self {
+        self.fixed_timestep = self.fixed_timestep.with_max_steps(max_steps);
+        self
+    }
+    /// caps the frame rate of windowed apps, headless apps always run unlimited
+    ///
+    /// [`FrameLimit::Vsync`] also switches the renderer to [`PresentMode::Fifo`], a
+    /// [`FrameLimit::Fps`] that isn't a positive number runs unlimited
+    pub fn with_frame_limit(mut self, mut limit: FrameLimit) -> Self {
+        if let FrameLimit::Fps(fps) = limit {
+            if !is_valid_fps(fps) {
+                warning!("frame limit of {fps} fps isn't a positive number, running unlimited");
+                limit = FrameLimit::Unlimited;
+            }
+        }
+        if limit == FrameLimit::Vsync {
+            self.renderer_config.present_mode = PresentMode::Fifo;
+        }
+        self.frame_pacer.limit = limit;
+        self
+    }
+    /// the frame rate while the window is unfocused, `None` keeps the frame limit, defaults to
+    /// [`crate::runtime::pacing::DEFAULT_UNFOCUSED_FPS`], a rate that isn't a positive number
+    /// also keeps the frame limit
+    pub fn with_unfocused_fps(mut self, mut fps: Option<f64>) -> Self {
+        if let Some(invalid) = fps.filter(|&fps| !is_valid_fps(fps)) {
+            warning!("unfocused frame rate of {invalid} fps isn't a positive number, ignoring it");
+            fps = None;
+        }
+        self.frame_pacer.unfocused_fps = fps;
+        self
+    }
+    /// records the input and window events, the recording is saved to `path` when the app
+    /// closes, see [`Recording`]
+    pub fn with_event_recording(mut self, path: impl AsRef<Path>) -> Self {
+        self.recording_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+    /// feeds the events of a recording made with [`AppBuilder::with_event_recording`] back at
+    /// the recorded frames, usually with a headless app stopping on
+    /// [`crate::App::replay_finished`]
+    pub fn with_replay(mut self, path: impl AsRef<Path>) -> Self {
+        self.replay_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+    pub fn build(mut self) -> Result<crate::App, EngineError> {
+        if let Some(path) = &self.icon_path {
+            self.window_data.icon = Some(load_icon(path)?);
+        }
+        let mut app = crate::App::new(self.window_data);
+        app.recorder = self
+            .recording_path
+            .map(|path| EventRecorder::new(Some(path)));
+        if let Some(path) = &self.replay_path {
+            app.replay = Some(EventReplay::new(Recording::load(path)?));
+        }
+        app.headless = self.headless;
+        app.renderer_config = self.renderer_config;
+        app.fixed_timestep = self.fixed_timestep;
+        app.frame_pacer = self.frame_pacer;
 
         for hook in self.init_hooks.into_iter() {
             (hook)(&mut app)?;
         }
-        for layer in self.layers {
-            app.add_layer(layer);
+        for (layer, overlay) in self.layers {
+            match overlay {
+                true => app.add_overlay(layer),
+                false => app.add_layer(layer),
+            };
         }
 
-        return Ok(app);
+        Ok(app)
     }
 }
 
+/// How the window covers the screen
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// a borderless window covering the current monitor
+    Borderless,
+    /// takes over the monitor with the video mode closest to the window size
+    Exclusive,
+}
+
+/// Restricts the cursor to the window
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CursorGrab {
+    #[default]
+    None,
+    /// the cursor can't leave the window
+    Confined,
+    /// the cursor can't move, use mouse motion events to look around
+    Locked,
+}
+
 pub struct WindowData {
-    pub title: &'static str,
+    pub title: String,
     pub size: (u32, u32),
+    pub resizable: bool,
+    pub fullscreen: Option<FullscreenMode>,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub cursor_grab: CursorGrab,
+    pub cursor_visible: bool,
+    pub icon: Option<Icon>,
 }
 
 impl Default for WindowData {
     fn default() -> Self {
         WindowData {
-            title: "window",
+            title: "window".to_string(),
             size: (1280, 720),
+            resizable: true,
+            fullscreen: None,
+            min_size: None,
+            max_size: None,
+            cursor_grab: CursorGrab::None,
+            cursor_visible: true,
+            icon: None,
+        }
+    }
+}
+
+impl WindowData {
+    pub(crate) fn attributes(&self, event_loop: &ActiveEventLoop) -> WindowAttributes {
+        let physical = |(width, height): (u32, u32)| {
+            winit::dpi::Size::Physical(winit::dpi::PhysicalSize { width, height })
+        };
+        let mut attributes = WindowAttributes::default()
+            .with_title(self.title.clone())
+            .with_inner_size(physical(self.size))
+            .with_resizable(self.resizable)
+            .with_window_icon(self.icon.clone())
+            .with_fullscreen(
+                self.fullscreen
+                    .and_then(|mode| fullscreen(mode, self.size, event_loop)),
+            );
+        if let Some(min_size) = self.min_size {
+            attributes = attributes.with_min_inner_size(physical(min_size));
+        }
+        if let Some(max_size) = self.max_size {
+            attributes = attributes.with_max_inner_size(physical(max_size));
         }
+        attributes
+    }
+    /// applies the cursor options, they can only be set once the window exists
+    pub(crate) fn apply_cursor(&self, window: &Window) {
+        set_cursor_grab(window, self.cursor_grab);
+        window.set_cursor_visible(self.cursor_visible);
     }
 }
 
+/// the winit fullscreen mode for `mode`, exclusive fullscreen picks the video mode of the
+/// primary monitor closest to `size` and falls back to borderless without a monitor
+pub(crate) fn fullscreen(
+    mode: FullscreenMode,
+    size: (u32, u32),
+    event_loop: &ActiveEventLoop,
+) -> Option<Fullscreen> {
+    match mode {
+        FullscreenMode::Borderless => Some(Fullscreen::Borderless(None)),
+        FullscreenMode::Exclusive => {
+            let monitor = event_loop
+                .primary_monitor()
+                .or_else(|| event_loop.available_monitors().next());
+            let video_mode = monitor.and_then(|monitor| {
+                monitor.video_modes().min_by_key(|video_mode| {
+                    let video_size = video_mode.size();
+                    (
+                        video_size.width.abs_diff(size.0) + video_size.height.abs_diff(size.1),
+                        u32::MAX - video_mode.refresh_rate_millihertz(),
+                    )
+                })
+            });
+            match video_mode {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                None => {
+                    warning!("no video mode available for exclusive fullscreen, using borderless");
+                    Some(Fullscreen::Borderless(None))
+                }
+            }
+        }
+    }
+}
+
+/// grabs the cursor, locking falls back to confining on platforms that can't lock it
+pub(crate) fn set_cursor_grab(window: &Window, grab: CursorGrab) {
+    use winit::window::CursorGrabMode;
+    let result = match grab {
+        CursorGrab::None => window.set_cursor_grab(CursorGrabMode::None),
+        CursorGrab::Confined => window.set_cursor_grab(CursorGrabMode::Confined),
+        CursorGrab::Locked => window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+    };
+    if let Err(e) = result {
+        warning!("failed to grab the cursor: {}", e);
+    }
+}
+
+fn load_icon(path: &Path) -> Result<Icon, EngineError> {
+    let image = image::open(path)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height).map_err(EngineError::InvalidIcon)
+}
+
 pub mod initializers {
     use super::*;
     use crate::core::events;
@@ -74,5 +387,63 @@ pub mod initializers {
                 Ok(())
             })
         }
+        /// adds a closure listener, see [`events::EventSystem::on`]
+        ///
+        /// ```ignore
+        /// App::begin_build().on(|event: &WindowResize| println!("resized to {:?}", event.0))
+        /// ```
+        pub fn on<T, R, F>(self, f: F) -> Self
+        where
+            T: events::event::EventMarker + 'static,
+            R: Into<events::EventEvaluateState>,
+            F: FnMut(&T) -> R + 'static,
+        {
+            self.add_init_hook(move |app| {
+                app.event_system.on(f);
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_builder_test() {
+        let app = AppBuilder::new()
+            .with_title("game")
+            .with_size(800, 600)
+            .with_resizable(false)
+            .with_fullscreen(FullscreenMode::Borderless)
+            .with_cursor_grab(CursorGrab::Locked)
+            .with_vsync(true)
+            .headless()
+            .build()
+            .unwrap();
+
+        assert_eq!(app.window_data.title, "game");
+        assert_eq!(app.window_data.size, (800, 600));
+        assert!(!app.window_data.resizable);
+        assert_eq!(app.window_data.fullscreen, Some(FullscreenMode::Borderless));
+        assert_eq!(app.window_data.cursor_grab, CursorGrab::Locked);
+        assert_eq!(app.renderer_config.present_mode, PresentMode::Fifo);
+    }
+
+    #[test]
+    fn window_icon_test() {
+        let path = std::env::temp_dir().join(format!(
+            "engine_lib_window_icon_test-{}.png",
+            std::process::id()
+        ));
+        image::RgbaImage::new(16, 16).save(&path).unwrap();
+
+        let app = AppBuilder::new().with_icon(&path).build().unwrap();
+        assert!(app.window_data.icon.is_some());
+
+        let missing = AppBuilder::new().with_icon(path.with_extension("missing.png"));
+        assert!(matches!(missing.build(), Err(EngineError::Image(_))));
+        _ = std::fs::remove_file(&path);
     }
 }
@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{Layer, LayerId};
+
+/// Where a layer is added to a [`super::LayerStack`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LayerPosition {
+    /// above the other layers but below the overlays
+    Top,
+    /// above everything else
+    Overlay,
+    Before(LayerId),
+    After(LayerId),
+}
+
+/// A change to the layers requested through [`LayerCommands`], applied by the app before the
+/// next frame
+pub(crate) enum LayerCommand {
+    Add {
+        id: LayerId,
+        position: LayerPosition,
+        layer: Box<dyn Layer>,
+    },
+    Remove(LayerId),
+    SetEnabled(LayerId, bool),
+}
+
+struct LayerCommandState {
+    next_id: u64,
+    commands: Vec<LayerCommand>,
+}
+
+/// Changes the app's layers while they are updated, get one with [`crate::App::layer_commands`]
+/// or from [`super::UpdateContext::layers`]
+///
+/// changes are queued and applied before the next frame, the ids of added layers are known right
+/// away
+#[derive(Clone)]
+pub struct LayerCommands {
+    state: Rc<RefCell<LayerCommandState>>,
+}
+
+impl LayerCommands {
+    pub(crate) fn new() -> Self {
+        LayerCommands {
+            state: Rc::new(RefCell::new(LayerCommandState {
+                next_id: 0,
+                commands: vec![],
+            })),
+        }
+    }
+    /// the id of the next layer added, shared with the [`super::LayerStack`] so ids of queued
+    /// layers don't collide with the ones added directly
+    pub(crate) fn next_id(&self) -> LayerId {
+        let mut state = self.state.borrow_mut();
+        state.next_id += 1;
+        LayerId(state.next_id - 1)
+    }
+    fn add(&self, position: LayerPosition, layer: Box<dyn Layer>) -> LayerId {
+        let id = self.next_id();
+        self.state.borrow_mut().commands.push(LayerCommand::Add {
+            id,
+            position,
+            layer,
+        });
+        id
+    }
+
+    /// adds a layer above the other layers but below the overlays, see [`crate::App::add_layer`]
+    pub fn add_layer(&self, layer: Box<dyn Layer>) -> LayerId {
+        self.add(LayerPosition::Top, layer)
+    }
+    /// adds a layer above every other layer, see [`crate::App::add_overlay`]
+    pub fn add_overlay(&self, layer: Box<dyn Layer>) -> LayerId {
+        self.add(LayerPosition::Overlay, layer)
+    }
+    /// adds a layer right below `other`, the layer is dropped without being initialized when
+    /// `other` isn't in the app by then
+    pub fn insert_layer_before(&self, other: LayerId, layer: Box<dyn Layer>) -> LayerId {
+        self.add(LayerPosition::Before(other), layer)
+    }
+    /// adds a layer right above `other`, the layer is dropped without being initialized when
+    /// `other` isn't in the app by then
+    pub fn insert_layer_after(&self, other: LayerId, layer: Box<dyn Layer>) -> LayerId {
+        self.add(LayerPosition::After(other), layer)
+    }
+    /// removes a layer and closes it, see [`crate::App::remove_layer`]
+    pub fn remove_layer(&self, id: LayerId) {
+        self.state
+            .borrow_mut()
+            .commands
+            .push(LayerCommand::Remove(id));
+    }
+    /// see [`crate::App::set_layer_enabled`]
+    pub fn set_layer_enabled(&self, id: LayerId, enabled: bool) {
+        self.state
+            .borrow_mut()
+            .commands
+            .push(LayerCommand::SetEnabled(id, enabled));
+    }
+    /// the number of changes waiting for the next frame
+    pub fn queued_len(&self) -> usize {
+        self.state.borrow().commands.len()
+    }
+
+    pub(crate) fn take_commands(&self) -> Vec<LayerCommand> {
+        std::mem::take(&mut self.state.borrow_mut().commands)
+    }
+}
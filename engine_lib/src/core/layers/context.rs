@@ -0,0 +1,44 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::LayerCommands;
+use crate::core::events::EventSystem;
+use crate::renderer::Renderer;
+use crate::runtime::input::InputSystem;
+
+/// What a layer can access while it is updated
+pub struct UpdateContext<'a> {
+    pub event_system: &'a mut EventSystem,
+    /// adds, removes or disables layers before the next frame
+    pub layers: LayerCommands,
+    /// draws with [`Renderer::draw_mesh`], only available once the window and its renderer were
+    /// created, see [`super::Layer::renderer_ready`]
+    pub renderer: Option<&'a mut Renderer>,
+    /// only available when the app was built with the input system
+    pub input: Option<&'a Arc<Mutex<InputSystem>>>,
+    /// the number of the frame being updated, starts at 1
+    pub frame: u64,
+    /// the time since the previous frame, zero on the first frame
+    pub delta: Duration,
+    /// the time since the first frame
+    pub elapsed: Duration,
+    /// how far the frame is between the last fixed update and the next, from 0 to 1, used to
+    /// interpolate what was simulated in [`super::Layer::fixed_update`]
+    pub alpha: f32,
+}
+
+impl UpdateContext<'_> {
+    /// [`UpdateContext::delta`] in seconds
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+    /// the renderer, `None` in headless apps and before the window was created
+    pub fn renderer(&mut self) -> Option<&mut Renderer> {
+        self.renderer.as_deref_mut()
+    }
+    /// locks the input system, `None` when the app was built without it
+    pub fn input(&self) -> Option<MutexGuard<'_, InputSystem>> {
+        self.input
+            .map(|input| input.lock().expect("failed to aquire lock"))
+    }
+}
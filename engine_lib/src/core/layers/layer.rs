@@ -1,10 +1,35 @@
+use crate::core::events::{Event, EventEvaluateState};
+
 /// Represents an engine layer, layers are distinct 'layers' of the engine, allowing for easy
 /// injection of custom systems
 pub trait Layer {
     /// called either when the app is initialized, or when the layer is added
     fn init(&mut self, app: &mut crate::App);
-    /// the layer's update loop
-    fn update(&mut self);
+    /// called once the renderer exists, or right after [`Layer::init`] when the layer is added
+    /// later, use it to upload meshes with [`crate::renderer::Renderer::upload_mesh`]
+    ///
+    /// headless apps have no renderer and never call it
+    fn renderer_ready(&mut self, _renderer: &mut crate::renderer::Renderer) {}
+    /// the layer's update loop, called once per frame
+    fn update(&mut self, ctx: &mut super::UpdateContext);
+    /// called at a fixed rate before [`Layer::update`], zero or more times per frame, use it for
+    /// physics and gameplay that must not depend on the frame rate
+    ///
+    /// `ctx.delta` is the fixed step
+    fn fixed_update(&mut self, _ctx: &mut super::UpdateContext) {}
+    /// receives the events the app dispatches before the event listeners, layers are visited
+    /// from the top of the stack down, overlays first
+    ///
+    /// that is every queued event and the blocking events from the window, blocking events
+    /// executed on the event system itself, e.g. with
+    /// [`crate::core::events::EventSystem::execute`] from [`super::UpdateContext::event_system`],
+    /// skip the layers and only reach the listeners
+    ///
+    /// returning [`EventEvaluateState::Handled`] or [`EventEvaluateState::Cancelled`] stops the
+    /// event from reaching the layers below and the listeners
+    fn on_event(&mut self, _event: &Event) -> EventEvaluateState {
+        EventEvaluateState::Unhandled
+    }
     /// called when the layer is being removed, either manually or on shutdown
     fn close(&mut self);
 }
@@ -1,3 +1,9 @@
+mod commands;
+mod context;
 mod layer;
+mod stack;
 
+pub use commands::*;
+pub use context::*;
 pub use layer::*;
+pub use stack::*;
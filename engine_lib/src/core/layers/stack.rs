@@ -0,0 +1,388 @@
+use super::{Layer, LayerCommands, LayerPosition};
+use crate::core::events::{Event, EventEvaluateState, EventSink};
+
+/// Refers to a layer added to a [`LayerStack`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LayerId(pub(super) u64);
+
+struct LayerEntry {
+    id: LayerId,
+    layer: Box<dyn Layer>,
+    enabled: bool,
+}
+
+/// The ordered layers of an app, from the bottom to the top
+///
+/// overlays always sit above the regular layers, e.g. a UI drawn over the game
+pub struct LayerStack {
+    entries: Vec<LayerEntry>,
+    /// the index of the first overlay
+    overlay_start: usize,
+    commands: LayerCommands,
+}
+
+impl LayerStack {
+    pub fn new() -> Self {
+        LayerStack {
+            entries: vec![],
+            overlay_start: 0,
+            commands: LayerCommands::new(),
+        }
+    }
+    fn index_of(&self, id: LayerId) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+    /// adds a layer with an id taken from [`LayerStack::commands`], returns the layer back when
+    /// the layer it is placed next to isn't in the stack
+    pub(crate) fn insert_at(
+        &mut self,
+        id: LayerId,
+        position: LayerPosition,
+        layer: Box<dyn Layer>,
+    ) -> Result<(), Box<dyn Layer>> {
+        let (index, overlay) = match position {
+            LayerPosition::Top => (self.overlay_start, false),
+            LayerPosition::Overlay => (self.entries.len(), true),
+            LayerPosition::Before(other) | LayerPosition::After(other) => {
+                let Some(index) = self.index_of(other) else {
+                    return Err(layer);
+                };
+                let overlay = index >= self.overlay_start;
+                match position {
+                    LayerPosition::After(_) => (index + 1, overlay),
+                    _ => (index, overlay),
+                }
+            }
+        };
+        self.entries.insert(
+            index,
+            LayerEntry {
+                id,
+                layer,
+                enabled: true,
+            },
+        );
+        if !overlay {
+            self.overlay_start += 1;
+        }
+        Ok(())
+    }
+    fn insert(
+        &mut self,
+        position: LayerPosition,
+        layer: Box<dyn Layer>,
+    ) -> Result<LayerId, Box<dyn Layer>> {
+        let id = self.commands.next_id();
+        self.insert_at(id, position, layer).map(|()| id)
+    }
+
+    /// adds a layer above the other layers but below the overlays
+    pub fn push(&mut self, layer: Box<dyn Layer>) -> LayerId {
+        let id = self.commands.next_id();
+        // only placing a layer next to another one can fail
+        _ = self.insert_at(id, LayerPosition::Top, layer);
+        id
+    }
+    /// adds a layer above everything else
+    pub fn push_overlay(&mut self, layer: Box<dyn Layer>) -> LayerId {
+        let id = self.commands.next_id();
+        // only placing a layer next to another one can fail
+        _ = self.insert_at(id, LayerPosition::Overlay, layer);
+        id
+    }
+    /// adds a layer right below `other`, the layer is an overlay when `other` is one, returns
+    /// the layer back when `other` isn't in the stack
+    pub fn insert_before(
+        &mut self,
+        other: LayerId,
+        layer: Box<dyn Layer>,
+    ) -> Result<LayerId, Box<dyn Layer>> {
+        self.insert(LayerPosition::Before(other), layer)
+    }
+    /// adds a layer right above `other`, the layer is an overlay when `other` is one, returns
+    /// the layer back when `other` isn't in the stack
+    pub fn insert_after(
+        &mut self,
+        other: LayerId,
+        layer: Box<dyn Layer>,
+    ) -> Result<LayerId, Box<dyn Layer>> {
+        self.insert(LayerPosition::After(other), layer)
+    }
+    /// queues changes to this stack, they are applied by the app before the next frame
+    pub fn commands(&self) -> LayerCommands {
+        self.commands.clone()
+    }
+    /// takes a layer out of the stack, [`Layer::close`] isn't called
+    pub fn remove(&mut self, id: LayerId) -> Option<Box<dyn Layer>> {
+        let index = self.index_of(id)?;
+        if index < self.overlay_start {
+            self.overlay_start -= 1;
+        }
+        Some(self.entries.remove(index).layer)
+    }
+    /// disabled layers stay in the stack but aren't updated, returns false when the layer isn't
+    /// in the stack
+    pub fn set_enabled(&mut self, id: LayerId, enabled: bool) -> bool {
+        match self.index_of(id) {
+            Some(index) => {
+                self.entries[index].enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn is_enabled(&self, id: LayerId) -> Option<bool> {
+        self.index_of(id).map(|index| self.entries[index].enabled)
+    }
+    pub fn contains(&self, id: LayerId) -> bool {
+        self.index_of(id).is_some()
+    }
+    pub fn is_overlay(&self, id: LayerId) -> Option<bool> {
+        self.index_of(id).map(|index| index >= self.overlay_start)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// the ids from the bottom to the top
+    pub fn ids(&self) -> impl Iterator<Item = LayerId> + '_ {
+        self.entries.iter().map(|entry| entry.id)
+    }
+    /// the enabled layers from the bottom to the top
+    pub fn iter_enabled_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Layer>> {
+        self.entries
+            .iter_mut()
+            .filter(|entry| entry.enabled)
+            .map(|entry| &mut entry.layer)
+    }
+    /// every layer, enabled or not, from the bottom to the top
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Layer>> {
+        self.entries.iter_mut().map(|entry| &mut entry.layer)
+    }
+}
+
+/// dispatches events to the enabled layers from the top down
+impl EventSink for LayerStack {
+    fn dispatch(&mut self, event: &Event) -> EventEvaluateState {
+        for entry in self.entries.iter_mut().rev().filter(|entry| entry.enabled) {
+            let state = entry.layer.on_event(event);
+            if state != EventEvaluateState::Unhandled {
+                return state;
+            }
+        }
+        EventEvaluateState::Unhandled
+    }
+}
+
+impl Default for LayerStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::event::MouseEvent;
+    use crate::core::events::keyboard::KeyState;
+    use crate::core::events::mouse::MouseButton;
+    use crate::core::events::{listener_from_func, EventInfo};
+    use crate::core::layers::UpdateContext;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct EmptyLayer;
+    impl Layer for EmptyLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, _ctx: &mut UpdateContext) {}
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn layer_stack_order_test() {
+        let mut stack = LayerStack::new();
+
+        let overlay = stack.push_overlay(Box::new(EmptyLayer));
+        let a = stack.push(Box::new(EmptyLayer));
+        let b = stack.push(Box::new(EmptyLayer));
+        let before_b = stack.insert_before(b, Box::new(EmptyLayer)).ok().unwrap();
+        let after_b = stack.insert_after(b, Box::new(EmptyLayer)).ok().unwrap();
+        let top = stack.push_overlay(Box::new(EmptyLayer));
+        let before_overlay = stack
+            .insert_before(overlay, Box::new(EmptyLayer))
+            .ok()
+            .unwrap();
+
+        assert_eq!(
+            stack.ids().collect::<Vec<_>>(),
+            vec![a, before_b, b, after_b, before_overlay, overlay, top]
+        );
+        assert_eq!(stack.is_overlay(after_b), Some(false));
+        assert_eq!(stack.is_overlay(before_overlay), Some(true));
+        assert_eq!(stack.is_overlay(overlay), Some(true));
+        assert!(stack.remove(before_overlay).is_some());
+
+        assert!(stack.remove(before_b).is_some());
+        assert!(stack.remove(before_b).is_none());
+        let c = stack.push(Box::new(EmptyLayer));
+        assert_eq!(
+            stack.ids().collect::<Vec<_>>(),
+            vec![a, b, after_b, c, overlay, top]
+        );
+    }
+
+    #[test]
+    fn layer_stack_enabled_test() {
+        let mut stack = LayerStack::new();
+        let a = stack.push(Box::new(EmptyLayer));
+        stack.push(Box::new(EmptyLayer));
+
+        assert!(stack.set_enabled(a, false));
+        assert_eq!(stack.is_enabled(a), Some(false));
+        assert_eq!(stack.iter_enabled_mut().count(), 1);
+        assert_eq!(stack.iter_mut().count(), 2);
+    }
+
+    struct TrackedLayer {
+        updates: Rc<Cell<u32>>,
+        closed: Rc<Cell<bool>>,
+    }
+    impl Layer for TrackedLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, _ctx: &mut UpdateContext) {
+            self.updates.set(self.updates.get() + 1);
+        }
+        fn close(&mut self) {
+            self.closed.set(true);
+        }
+    }
+
+    #[test]
+    fn app_remove_and_disable_layer_test() {
+        let updates = Rc::new(Cell::new(0));
+        let closed = Rc::new(Cell::new(false));
+        let mut app = crate::App::begin_build().headless().build().unwrap();
+        let id = app.add_layer(Box::new(TrackedLayer {
+            updates: updates.clone(),
+            closed: closed.clone(),
+        }));
+
+        app.tick();
+        app.set_layer_enabled(id, false);
+        app.tick();
+        assert_eq!(updates.get(), 1);
+
+        assert!(app.remove_layer(id));
+        assert!(closed.get());
+        assert!(!app.remove_layer(id));
+    }
+
+    /// changes the other layers on its first update
+    struct ManagerLayer {
+        remove: LayerId,
+        disable: LayerId,
+        added: Rc<Cell<u32>>,
+        closed: Rc<Cell<bool>>,
+    }
+    impl Layer for ManagerLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, ctx: &mut UpdateContext) {
+            if ctx.frame == 1 {
+                ctx.layers.remove_layer(self.remove);
+                ctx.layers.set_layer_enabled(self.disable, false);
+                ctx.layers.add_layer(Box::new(TrackedLayer {
+                    updates: self.added.clone(),
+                    closed: self.closed.clone(),
+                }));
+            }
+        }
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn layer_commands_from_update_test() {
+        let tracked = || (Rc::new(Cell::new(0)), Rc::new(Cell::new(false)));
+        let tracked_layer = |(updates, closed): &(Rc<Cell<u32>>, Rc<Cell<bool>>)| {
+            Box::new(TrackedLayer {
+                updates: updates.clone(),
+                closed: closed.clone(),
+            })
+        };
+        let (removed, disabled, added) = (tracked(), tracked(), tracked());
+        let mut app = crate::App::begin_build().headless().build().unwrap();
+        let remove = app.add_layer(tracked_layer(&removed));
+        let disable = app.add_layer(tracked_layer(&disabled));
+        app.add_layer(Box::new(ManagerLayer {
+            remove,
+            disable,
+            added: added.0.clone(),
+            closed: added.1.clone(),
+        }));
+
+        app.tick();
+        assert_eq!(app.layer_commands().queued_len(), 3);
+        app.tick();
+        app.tick();
+        assert_eq!((removed.0.get(), removed.1.get()), (1, true));
+        assert_eq!(disabled.0.get(), 1);
+        assert_eq!(app.is_layer_enabled(disable), Some(false));
+        assert_eq!(added.0.get(), 2);
+        assert_eq!(app.layer_commands().queued_len(), 0);
+    }
+
+    struct ClickLayer {
+        clicks: Rc<Cell<u32>>,
+        swallow: bool,
+    }
+    impl Layer for ClickLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, _ctx: &mut UpdateContext) {}
+        fn on_event(&mut self, event: &Event) -> EventEvaluateState {
+            if event.downcast::<MouseEvent>().is_none() {
+                return EventEvaluateState::Unhandled;
+            }
+            self.clicks.set(self.clicks.get() + 1);
+            match self.swallow {
+                true => EventEvaluateState::Handled,
+                false => EventEvaluateState::Unhandled,
+            }
+        }
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn overlay_swallows_event_test() {
+        let game_clicks = Rc::new(Cell::new(0));
+        let ui_clicks = Rc::new(Cell::new(0));
+        let listener_clicks = Rc::new(Cell::new(0));
+        let mut app = crate::App::begin_build().headless().build().unwrap();
+        let counter = listener_clicks.clone();
+        app.event_system
+            .add_listener::<MouseEvent, _>(Box::new(listener_from_func(move |_| {
+                counter.set(counter.get() + 1);
+                EventEvaluateState::Unhandled
+            })));
+        let ui = app.add_overlay(Box::new(ClickLayer {
+            clicks: ui_clicks.clone(),
+            swallow: true,
+        }));
+        app.add_layer(Box::new(ClickLayer {
+            clicks: game_clicks.clone(),
+            swallow: false,
+        }));
+
+        let click = || EventInfo::queued(MouseEvent(MouseButton::Left, KeyState::Down));
+        app.event_system.queue_event(click());
+        app.tick();
+        assert_eq!((ui_clicks.get(), game_clicks.get()), (1, 0));
+        assert_eq!(listener_clicks.get(), 0);
+
+        app.set_layer_enabled(ui, false);
+        app.event_system.queue_event(click());
+        app.tick();
+        assert_eq!((ui_clicks.get(), game_clicks.get()), (1, 1));
+        assert_eq!(listener_clicks.get(), 1);
+    }
+}
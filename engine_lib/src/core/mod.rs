@@ -1,4 +1,5 @@
 pub mod datatypes;
+pub mod error;
 pub mod events;
 pub mod initialization;
 pub mod layers;
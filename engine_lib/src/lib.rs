@@ -1,10 +1,11 @@
-use renderer::Renderer;
-use winit;
+// lets `#[derive(Event)]` refer to `::engine_lib` inside this crate
+extern crate self as engine_lib;
+
 use winit::application::ApplicationHandler;
 use winit::event::{DeviceEvent, WindowEvent};
 use winit::event_loop;
-use winit::event_loop::ActiveEventLoop;
-use winit::window::{self, WindowAttributes};
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
+use winit::window;
 
 pub mod core;
 #[doc(hidden)]
@@ -12,22 +13,39 @@ pub mod external;
 pub mod renderer;
 pub mod runtime;
 
+use core::error::EngineError;
 use core::events::EventSystem;
 use core::initialization::{AppBuilder, WindowData};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 pub struct App {
     window: Option<Arc<window::Window>>,
     window_data: WindowData,
+    window_handle: runtime::window::WindowHandle,
     pub event_system: core::events::EventSystem,
     frame_num: u64,
-    layers: Vec<Box<dyn core::layers::Layer>>,
+    /// when the first frame started
+    start_time: Option<Instant>,
+    /// when the previous frame started
+    last_frame_time: Option<Instant>,
+    fixed_timestep: runtime::timestep::FixedTimestep,
+    frame_pacer: runtime::pacing::FramePacer,
+    focused: bool,
+    layers: core::layers::LayerStack,
     input: Option<Arc<Mutex<runtime::input::InputSystem>>>,
     renderer: Option<renderer::Renderer>,
+    headless: Option<runtime::headless::HeadlessConfig>,
+    renderer_config: renderer::RendererConfig,
+    recorder: Option<runtime::recording::EventRecorder>,
+    replay: Option<runtime::recording::EventReplay>,
+    /// set when the app had to stop inside the event loop, returned from [`App::run`]
+    error: Option<EngineError>,
 }
 
 fn init() {
-    env_logger::init();
+    // the logger may already be set when more than one app is created in a process, e.g. in tests
+    _ = env_logger::try_init();
 }
 
 impl App {
@@ -36,12 +54,28 @@ impl App {
         init();
         App {
             window: None,
+            window_handle: runtime::window::WindowHandle::new(
+                window_data.size,
+                window_data.fullscreen,
+                window_data.cursor_grab,
+                window_data.cursor_visible,
+            ),
             window_data,
             event_system: EventSystem::new(),
             frame_num: 0,
-            layers: vec![],
+            start_time: None,
+            last_frame_time: None,
+            fixed_timestep: runtime::timestep::FixedTimestep::default(),
+            frame_pacer: runtime::pacing::FramePacer::default(),
+            focused: true,
+            layers: core::layers::LayerStack::new(),
             input: None,
             renderer: None,
+            headless: None,
+            renderer_config: renderer::RendererConfig::default(),
+            recorder: None,
+            replay: None,
+            error: None,
         }
     }
     pub fn begin_build() -> AppBuilder {
@@ -50,24 +84,11 @@ impl App {
 }
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        self.window = Some(
-            event_loop
-                .create_window(
-                    WindowAttributes::default()
-                        .with_resizable(false)
-                        .with_title(self.window_data.title)
-                        .with_inner_size(winit::dpi::Size::Physical(winit::dpi::PhysicalSize {
-                            width: self.window_data.size.0,
-                            height: self.window_data.size.1,
-                        })),
-                )
-                .expect("failed to create window")
-                .into(),
-        );
-        self.renderer = Some(
-            renderer::Renderer::init(self.window.as_ref().unwrap().clone())
-                .expect("failed to init renderer"),
-        );
+        if let Err(e) = self.create_window(event_loop) {
+            core::logging::engine::error!("failed to initialize: {}", e);
+            self.error = Some(e);
+            event_loop.exit();
+        }
     }
     fn device_event(
         &mut self,
@@ -77,13 +98,11 @@ impl ApplicationHandler for App {
     ) {
         match event {
             DeviceEvent::MouseMotion { delta } => self
-                .event_system
-                .queue_event::<core::events::event::MouseMotion>(core::events::EventInfo::queued(
+                .send_event::<core::events::event::MouseMotion>(core::events::EventInfo::queued(
                     core::events::event::MouseMotion((delta.0 as f32, delta.1 as f32)),
                 )),
             DeviceEvent::MouseWheel { delta } => self
-                .event_system
-                .queue_event::<core::events::event::MouseScroll>(core::events::EventInfo::queued(
+                .send_event::<core::events::event::MouseScroll>(core::events::EventInfo::queued(
                     core::events::event::MouseScroll(match delta {
                         winit::event::MouseScrollDelta::LineDelta(_, y) => y,
                         _ => return,
@@ -105,137 +124,399 @@ impl ApplicationHandler for App {
                 event,
                 is_synthetic: _,
             } => {
-                self.event_system
-                    .queue_event::<core::events::event::KeyboardEvent>(
-                        core::events::EventInfo::queued(core::events::event::KeyboardEvent(
-                            match event.physical_key {
-                                winit::keyboard::PhysicalKey::Code(c) => c,
-                                winit::keyboard::PhysicalKey::Unidentified(_) => return,
-                            },
-                            match event.state {
-                                winit::event::ElementState::Pressed if event.repeat => {
-                                    core::events::keyboard::KeyState::Repeat
-                                }
-                                winit::event::ElementState::Pressed => {
-                                    core::events::keyboard::KeyState::Down
-                                }
-                                winit::event::ElementState::Released => {
-                                    core::events::keyboard::KeyState::Up
-                                }
-                            },
-                        )),
-                    );
+                self.send_event::<core::events::event::KeyboardEvent>(
+                    core::events::EventInfo::queued(core::events::event::KeyboardEvent(
+                        match event.physical_key {
+                            winit::keyboard::PhysicalKey::Code(c) => c,
+                            winit::keyboard::PhysicalKey::Unidentified(_) => return,
+                        },
+                        match event.state {
+                            winit::event::ElementState::Pressed if event.repeat => {
+                                core::events::keyboard::KeyState::Repeat
+                            }
+                            winit::event::ElementState::Pressed => {
+                                core::events::keyboard::KeyState::Down
+                            }
+                            winit::event::ElementState::Released => {
+                                core::events::keyboard::KeyState::Up
+                            }
+                        },
+                    )),
+                );
             }
             WindowEvent::MouseInput {
                 device_id: _,
                 state,
                 button,
             } => {
-                self.event_system
-                    .queue_event::<core::events::event::MouseEvent>(
-                        core::events::EventInfo::queued(core::events::event::MouseEvent(
-                            match button {
-                                winit::event::MouseButton::Left => {
-                                    core::events::mouse::MouseButton::Left
-                                }
-                                winit::event::MouseButton::Right => {
-                                    core::events::mouse::MouseButton::Right
-                                }
-                                winit::event::MouseButton::Middle => {
-                                    core::events::mouse::MouseButton::Middle
-                                }
-                                winit::event::MouseButton::Forward => {
-                                    core::events::mouse::MouseButton::Forward
-                                }
-                                winit::event::MouseButton::Back => {
-                                    core::events::mouse::MouseButton::Back
-                                }
-                                _ => return,
-                            },
-                            match state {
-                                winit::event::ElementState::Pressed => {
-                                    core::events::keyboard::KeyState::Down
-                                }
-                                winit::event::ElementState::Released => {
-                                    core::events::keyboard::KeyState::Up
-                                }
-                            },
-                        )),
-                    );
+                self.send_event::<core::events::event::MouseEvent>(
+                    core::events::EventInfo::queued(core::events::event::MouseEvent(
+                        match button {
+                            winit::event::MouseButton::Left => {
+                                core::events::mouse::MouseButton::Left
+                            }
+                            winit::event::MouseButton::Right => {
+                                core::events::mouse::MouseButton::Right
+                            }
+                            winit::event::MouseButton::Middle => {
+                                core::events::mouse::MouseButton::Middle
+                            }
+                            winit::event::MouseButton::Forward => {
+                                core::events::mouse::MouseButton::Forward
+                            }
+                            winit::event::MouseButton::Back => {
+                                core::events::mouse::MouseButton::Back
+                            }
+                            _ => return,
+                        },
+                        match state {
+                            winit::event::ElementState::Pressed => {
+                                core::events::keyboard::KeyState::Down
+                            }
+                            winit::event::ElementState::Released => {
+                                core::events::keyboard::KeyState::Up
+                            }
+                        },
+                    )),
+                );
             }
-            WindowEvent::Resized(size) => self
-                .event_system
-                .queue_event::<core::events::event::WindowResize>(
+            WindowEvent::Resized(size) => {
+                self.window_handle.set_size((size.width, size.height));
+                if let Some(renderer) = self.renderer.as_mut() {
+                    renderer.window_resized();
+                }
+                self.send_event::<core::events::event::WindowResize>(
                     core::events::EventInfo::blocking(core::events::event::WindowResize((
                         size.width,
                         size.height,
                     ))),
-                ),
-            WindowEvent::Focused(focused) => match focused {
-                true => self
-                    .event_system
-                    .queue_event::<core::events::event::WindowFocus>(
+                )
+            }
+            WindowEvent::Focused(focused) => {
+                self.focused = focused;
+                match focused {
+                    true => self.send_event::<core::events::event::WindowFocus>(
                         core::events::EventInfo::blocking(core::events::event::WindowFocus),
                     ),
-                false => self
-                    .event_system
-                    .queue_event::<core::events::event::WindowLoseFocus>(
+                    false => self.send_event::<core::events::event::WindowLoseFocus>(
                         core::events::EventInfo::blocking(core::events::event::WindowLoseFocus),
                     ),
-            },
+                }
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.window_handle.set_scale_factor(scale_factor)
+            }
             WindowEvent::CloseRequested => {
-                self.event_system
-                    .queue_event(core::events::EventInfo::blocking(
-                        core::events::event::WindowClose,
-                    ));
+                self.send_event(core::events::EventInfo::blocking(
+                    core::events::event::WindowClose,
+                ));
                 self.close();
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested => {
-                self.event_system
-                    .queue_event(core::events::EventInfo::blocking(
-                        core::events::event::AppRender,
-                    ));
-                self.renderer
-                    .as_mut()
-                    .unwrap()
-                    .render(self.frame_num as usize);
+                self.send_event(core::events::EventInfo::blocking(
+                    core::events::event::AppRender,
+                ));
+                self.render();
             }
             _ => (),
         }
     }
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        self.frame_num += 1;
-        self.event_system.update();
-        self.layers.iter_mut().for_each(|l| l.update())
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window_handle.close_requested() {
+            self.send_event(core::events::EventInfo::blocking(
+                core::events::event::WindowClose,
+            ));
+            self.close();
+            event_loop.exit();
+            return;
+        }
+        let now = Instant::now();
+        if self.frame_pacer.should_run(now) {
+            self.apply_window_commands(event_loop);
+            self.tick();
+            if let Some(window) = self.window.as_ref() {
+                window.request_redraw();
+            }
+            self.frame_pacer.frame_started(now, self.focused);
+        }
+        event_loop.set_control_flow(match self.frame_pacer.next_frame() {
+            Some(next_frame) => ControlFlow::WaitUntil(next_frame),
+            None => ControlFlow::Poll,
+        });
     }
 }
 
 impl App {
-    pub fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let event_loop = event_loop::EventLoop::new().expect("failed to create event loop");
+    /// creates the window and the renderer drawing into it
+    fn create_window(&mut self, event_loop: &ActiveEventLoop) -> Result<(), EngineError> {
+        let window: Arc<window::Window> = event_loop
+            .create_window(self.window_data.attributes(event_loop))?
+            .into();
+        self.window_data.apply_cursor(&window);
+        let size = window.inner_size();
+        self.window_handle.set_size((size.width, size.height));
+        self.window_handle.set_scale_factor(window.scale_factor());
+        self.window = Some(window.clone());
+        self.set_renderer(renderer::Renderer::init(window, &self.renderer_config)?);
+        Ok(())
+    }
+    /// stores the renderer and lets every layer upload its meshes
+    fn set_renderer(&mut self, mut renderer: renderer::Renderer) {
+        self.layers
+            .iter_mut()
+            .for_each(|l| l.renderer_ready(&mut renderer));
+        self.renderer = Some(renderer);
+    }
+    /// draws the meshes queued by the layers
+    fn render(&mut self) {
+        if let Some(renderer) = self.renderer.as_mut() {
+            if let Err(e) = renderer.render(self.frame_num as usize) {
+                core::logging::engine::error!("failed to render frame: {}", e);
+            }
+        }
+    }
+    /// applies the changes requested through [`App::window_handle`]
+    fn apply_window_commands(&mut self, event_loop: &ActiveEventLoop) {
+        let commands = self.window_handle.take_commands();
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
+        for command in commands {
+            match command {
+                runtime::window::WindowCommand::Title(title) => window.set_title(&title),
+                runtime::window::WindowCommand::Fullscreen(fullscreen) => {
+                    window.set_fullscreen(fullscreen.and_then(|mode| {
+                        core::initialization::fullscreen(mode, self.window_data.size, event_loop)
+                    }))
+                }
+                runtime::window::WindowCommand::CursorGrab(grab) => {
+                    core::initialization::set_cursor_grab(window, grab)
+                }
+                runtime::window::WindowCommand::CursorVisible(visible) => {
+                    window.set_cursor_visible(visible)
+                }
+            }
+        }
+    }
+    /// runs the app until the window is closed, or until the headless stop condition is met
+    ///
+    /// errors that stop the event loop, e.g. a failure to create the window or the renderer, are
+    /// returned here
+    pub fn run(mut self) -> Result<(), EngineError> {
+        if let Some(config) = self.headless.take() {
+            self.run_headless(config);
+            self.cleanup();
+            return Ok(());
+        }
 
-        event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+        let event_loop = event_loop::EventLoop::new()?;
+
+        event_loop.set_control_flow(ControlFlow::Poll);
 
         let result = event_loop.run_app(&mut self);
+        let error = self.error.take();
         self.cleanup();
 
-        match result {
-            Ok(_) => Ok(()),
-            Err(e) => Err(Box::new(e)),
+        match (error, result) {
+            (Some(e), _) => Err(e),
+            (None, result) => Ok(result?),
+        }
+    }
+    fn run_headless(&mut self, mut config: runtime::headless::HeadlessConfig) {
+        while !config.should_stop(self) && !self.window_handle.close_requested() {
+            self.tick();
         }
+        self.close();
+    }
+    /// runs a single frame: processes queued events and updates every layer
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let start_time = *self.start_time.get_or_insert(now);
+        let delta = now - self.last_frame_time.unwrap_or(now);
+        self.last_frame_time = Some(now);
+
+        self.apply_layer_commands();
+        self.frame_num += 1;
+        if let Some(replay) = self.replay.as_mut() {
+            replay.queue_frame(self.frame_num, &mut self.event_system);
+        }
+        self.event_system.update_with(&mut self.layers);
+
+        let steps = self.fixed_timestep.advance(delta);
+        let mut ctx = core::layers::UpdateContext {
+            event_system: &mut self.event_system,
+            layers: self.layers.commands(),
+            renderer: self.renderer.as_mut(),
+            input: self.input.as_ref(),
+            frame: self.frame_num,
+            delta: self.fixed_timestep.step(),
+            elapsed: now - start_time,
+            alpha: 0.0,
+        };
+        for _ in 0..steps {
+            self.layers
+                .iter_enabled_mut()
+                .for_each(|l| l.fixed_update(&mut ctx));
+        }
+
+        ctx.delta = delta;
+        ctx.alpha = self.fixed_timestep.alpha();
+        self.layers
+            .iter_enabled_mut()
+            .for_each(|l| l.update(&mut ctx))
+    }
+    /// queues an event from the OS, blocking events are dispatched to the layers and listeners
+    /// right away
+    fn send_event<E: core::events::event::EventMarker>(
+        &mut self,
+        event: core::events::EventInfo<E>,
+    ) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            // blocking events are recorded as part of the next frame
+            recorder.record(self.frame_num + 1, &core::events::Event::new(&event.event));
+        }
+        self.event_system.queue_event_with(event, &mut self.layers);
+    }
+    /// the events recorded so far, `None` unless
+    /// [`core::initialization::AppBuilder::with_event_recording`] was used
+    pub fn recording(&self) -> Option<&runtime::recording::Recording> {
+        self.recorder.as_ref().map(|recorder| &recorder.recording)
+    }
+    /// true once every replayed event was queued, or when nothing is replayed
+    pub fn replay_finished(&self) -> bool {
+        self.replay
+            .as_ref()
+            .is_none_or(|replay| replay.is_finished())
+    }
+    /// how far the current frame is between two fixed updates, see
+    /// [`core::layers::UpdateContext::alpha`]
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.fixed_timestep.alpha()
+    }
+    /// the number of frames the app has run
+    pub fn frame(&self) -> u64 {
+        self.frame_num
+    }
+    /// controls the window, usable before the window is created
+    pub fn window_handle(&self) -> runtime::window::WindowHandle {
+        self.window_handle.clone()
+    }
+    /// the renderer, only available once the window has been created, layers get it through
+    /// [`core::layers::UpdateContext::renderer`]
+    pub fn renderer(&mut self) -> Option<&mut renderer::Renderer> {
+        self.renderer.as_mut()
     }
     /// closes the app, should be used for releasing resources used by vulkan
     pub fn cleanup(self) {}
     fn close(&mut self) {
-        self.layers.iter_mut().for_each(|l| l.close())
+        self.layers.iter_mut().for_each(|l| l.close());
+        if let Some(recorder) = self.recorder.as_ref() {
+            if let Err(e) = recorder.save() {
+                core::logging::engine::error!("failed to save the event recording: {}", e);
+            }
+        }
     }
 
-    /// adds a processing layer to the app
-    pub fn add_layer(&mut self, mut layer: Box<dyn core::layers::Layer>) -> &mut Self {
+    /// initializes and adds a layer, returns false without initializing the layer when the
+    /// layer it is placed next to isn't in the app
+    fn insert_layer(
+        &mut self,
+        id: core::layers::LayerId,
+        position: core::layers::LayerPosition,
+        mut layer: Box<dyn core::layers::Layer>,
+    ) -> bool {
+        if let core::layers::LayerPosition::Before(other)
+        | core::layers::LayerPosition::After(other) = position
+        {
+            if !self.layers.contains(other) {
+                return false;
+            }
+        }
         layer.init(self);
-        self.layers.push(layer);
-        self
+        if let Some(renderer) = self.renderer.as_mut() {
+            layer.renderer_ready(renderer);
+        }
+        self.layers.insert_at(id, position, layer).is_ok()
+    }
+    /// adds a processing layer to the app, above the other layers but below the overlays
+    pub fn add_layer(&mut self, layer: Box<dyn core::layers::Layer>) -> core::layers::LayerId {
+        let id = self.layers.commands().next_id();
+        self.insert_layer(id, core::layers::LayerPosition::Top, layer);
+        id
+    }
+    /// adds a layer above every other layer, e.g. a UI
+    pub fn add_overlay(&mut self, layer: Box<dyn core::layers::Layer>) -> core::layers::LayerId {
+        let id = self.layers.commands().next_id();
+        self.insert_layer(id, core::layers::LayerPosition::Overlay, layer);
+        id
+    }
+    /// adds a layer right below `other`, returns `None` without initializing the layer when
+    /// `other` isn't in the app
+    pub fn insert_layer_before(
+        &mut self,
+        other: core::layers::LayerId,
+        layer: Box<dyn core::layers::Layer>,
+    ) -> Option<core::layers::LayerId> {
+        let id = self.layers.commands().next_id();
+        self.insert_layer(id, core::layers::LayerPosition::Before(other), layer)
+            .then_some(id)
+    }
+    /// adds a layer right above `other`, returns `None` without initializing the layer when
+    /// `other` isn't in the app
+    pub fn insert_layer_after(
+        &mut self,
+        other: core::layers::LayerId,
+        layer: Box<dyn core::layers::Layer>,
+    ) -> Option<core::layers::LayerId> {
+        let id = self.layers.commands().next_id();
+        self.insert_layer(id, core::layers::LayerPosition::After(other), layer)
+            .then_some(id)
+    }
+    /// removes a layer and closes it, returns false when the layer isn't in the app
+    pub fn remove_layer(&mut self, id: core::layers::LayerId) -> bool {
+        match self.layers.remove(id) {
+            Some(mut layer) => {
+                layer.close();
+                true
+            }
+            None => false,
+        }
+    }
+    /// disabled layers aren't updated but stay in the app, returns false when the layer isn't
+    /// in the app
+    pub fn set_layer_enabled(&mut self, id: core::layers::LayerId, enabled: bool) -> bool {
+        self.layers.set_enabled(id, enabled)
+    }
+    /// `None` when the layer isn't in the app
+    pub fn is_layer_enabled(&self, id: core::layers::LayerId) -> Option<bool> {
+        self.layers.is_enabled(id)
+    }
+    /// changes the layers from inside a layer, the changes are applied before the next frame
+    pub fn layer_commands(&self) -> core::layers::LayerCommands {
+        self.layers.commands()
+    }
+    /// applies the changes requested through [`App::layer_commands`]
+    fn apply_layer_commands(&mut self) {
+        for command in self.layers.commands().take_commands() {
+            match command {
+                core::layers::LayerCommand::Add {
+                    id,
+                    position,
+                    layer,
+                } => {
+                    if !self.insert_layer(id, position, layer) {
+                        core::logging::engine::warning!(
+                            "dropping a queued layer, the layer it was placed next to is gone"
+                        );
+                    }
+                }
+                core::layers::LayerCommand::Remove(id) => _ = self.remove_layer(id),
+                core::layers::LayerCommand::SetEnabled(id, enabled) => {
+                    _ = self.set_layer_enabled(id, enabled)
+                }
+            }
+        }
     }
 }
 
@@ -0,0 +1,186 @@
+use std::ffi::CStr;
+
+use ash::{ext::debug_utils, vk, Entry};
+
+use super::device::{DeviceFeature, DeviceSelection};
+use super::frames::DEFAULT_FRAMES_IN_FLIGHT;
+use super::swapchain::PresentMode;
+use crate::core::logging::engine::warning;
+
+pub(crate) const VALIDATION_LAYER_NAME: &CStr = c"VK_LAYER_KHRONOS_validation";
+
+/// Options used when creating the vulkan instance and the renderer
+pub struct RendererConfig {
+    /// enables `VK_LAYER_KHRONOS_validation` and the debug messenger, ignored with a warning when
+    /// the layer isn't installed, defaults to on in debug builds only
+    pub validation: bool,
+    pub app_name: String,
+    /// packed with [`vk::make_api_version`]
+    pub app_version: u32,
+    pub engine_name: String,
+    /// packed with [`vk::make_api_version`]
+    pub engine_version: u32,
+    /// the highest vulkan version the app uses, defaults to 1.0
+    pub api_version: u32,
+    pub frames_in_flight: usize,
+    /// the preferred way frames are presented to the window
+    pub present_mode: PresentMode,
+    /// which physical device to use, [`super::DEVICE_ENV_VAR`] takes precedence when set
+    pub device: DeviceSelection,
+    /// devices missing any of these features are skipped
+    pub required_features: Vec<DeviceFeature>,
+    /// devices missing any of these extensions are skipped, the extensions are enabled on the
+    /// device
+    pub required_extensions: Vec<&'static CStr>,
+    /// debug messages with one of these ids aren't logged nor counted as validation errors
+    pub suppressed_message_ids: Vec<i32>,
+    /// debug messages with one of these id names, e.g. a `VUID`, aren't logged nor counted as
+    /// validation errors
+    pub suppressed_message_names: Vec<String>,
+}
+
+impl RendererConfig {
+    pub fn new() -> Self {
+        RendererConfig {
+            validation: cfg!(debug_assertions),
+            app_name: "app".to_string(),
+            app_version: 0,
+            engine_name: env!("CARGO_PKG_NAME").to_string(),
+            engine_version: vk::make_api_version(
+                0,
+                env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or(0),
+                env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or(0),
+                env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or(0),
+            ),
+            api_version: vk::API_VERSION_1_0,
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            present_mode: PresentMode::default(),
+            device: DeviceSelection::Auto,
+            required_features: vec![DeviceFeature::ShaderClipDistance],
+            required_extensions: vec![],
+            suppressed_message_ids: vec![],
+            suppressed_message_names: vec![],
+        }
+    }
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+    pub fn with_app_name(mut self, name: impl Into<String>) -> Self {
+        self.app_name = name.into();
+        self
+    }
+    pub fn with_app_version(mut self, major: u32, minor: u32, patch: u32) -> Self {
+        self.app_version = vk::make_api_version(0, major, minor, patch);
+        self
+    }
+    pub fn with_engine_name(mut self, name: impl Into<String>) -> Self {
+        self.engine_name = name.into();
+        self
+    }
+    pub fn with_engine_version(mut self, major: u32, minor: u32, patch: u32) -> Self {
+        self.engine_version = vk::make_api_version(0, major, minor, patch);
+        self
+    }
+    /// e.g. [`vk::API_VERSION_1_2`]
+    pub fn with_api_version(mut self, api_version: u32) -> Self {
+        self.api_version = api_version;
+        self
+    }
+    pub fn with_frames_in_flight(mut self, count: usize) -> Self {
+        self.frames_in_flight = count;
+        self
+    }
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+    pub fn with_device(mut self, device: DeviceSelection) -> Self {
+        self.device = device;
+        self
+    }
+    pub fn with_required_feature(mut self, feature: DeviceFeature) -> Self {
+        self.required_features.push(feature);
+        self
+    }
+    pub fn with_required_extension(mut self, extension: &'static CStr) -> Self {
+        self.required_extensions.push(extension);
+        self
+    }
+    pub fn with_suppressed_message_id(mut self, id: i32) -> Self {
+        self.suppressed_message_ids.push(id);
+        self
+    }
+    pub fn with_suppressed_message_name(mut self, name: impl Into<String>) -> Self {
+        self.suppressed_message_names.push(name.into());
+        self
+    }
+
+    /// whether validation should be enabled, checks that the layer and the `debug_utils`
+    /// extension are available on this machine
+    pub(crate) fn validation_supported(&self, entry: &Entry) -> bool {
+        if !self.validation {
+            return false;
+        }
+        let layers = unsafe { entry.enumerate_instance_layer_properties() }.unwrap_or_default();
+        if !has_layer(&layers, VALIDATION_LAYER_NAME) {
+            warning!(
+                "validation requested but {:?} isn't available, continuing without it",
+                VALIDATION_LAYER_NAME
+            );
+            return false;
+        }
+        let extensions =
+            unsafe { entry.enumerate_instance_extension_properties(None) }.unwrap_or_default();
+        if !extensions
+            .iter()
+            .any(|extension| extension.extension_name_as_c_str() == Ok(debug_utils::NAME))
+        {
+            warning!(
+                "validation requested but {:?} isn't available, continuing without it",
+                debug_utils::NAME
+            );
+            return false;
+        }
+        true
+    }
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn has_layer(layers: &[vk::LayerProperties], name: &CStr) -> bool {
+    layers
+        .iter()
+        .any(|layer| layer.layer_name_as_c_str() == Ok(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn has_layer_test() {
+        let layer = vk::LayerProperties::default()
+            .layer_name(VALIDATION_LAYER_NAME)
+            .unwrap();
+        assert!(has_layer(&[layer], VALIDATION_LAYER_NAME));
+        assert!(!has_layer(&[], VALIDATION_LAYER_NAME));
+        assert!(!has_layer(&[layer], c"VK_LAYER_other"));
+    }
+
+    #[test]
+    fn config_defaults_test() {
+        let config = RendererConfig::default();
+        assert_eq!(config.validation, cfg!(debug_assertions));
+        assert_eq!(config.api_version, vk::API_VERSION_1_0);
+        assert_eq!(config.frames_in_flight, DEFAULT_FRAMES_IN_FLIGHT);
+        assert_eq!(
+            vk::api_version_minor(config.engine_version),
+            env!("CARGO_PKG_VERSION_MINOR").parse::<u32>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+use std::ffi;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ash::vk;
+
+use super::Renderer;
+
+/// The `log` target vulkan debug messages are logged under
+pub const VULKAN_LOG_TARGET: &str = "ENGINE::vulkan";
+
+/// Shared with the debug messenger callback through its user data, must outlive the messenger
+pub struct DebugMessengerState {
+    /// messages with one of these ids are dropped, e.g. `0x609a13b`
+    pub suppressed_message_ids: Vec<i32>,
+    /// messages with one of these id names are dropped, e.g. `UNASSIGNED-BestPractices-vkAllocateMemory-small-allocation`
+    pub suppressed_message_names: Vec<String>,
+    validation_errors: AtomicU64,
+}
+
+impl DebugMessengerState {
+    pub fn new(suppressed_message_ids: Vec<i32>, suppressed_message_names: Vec<String>) -> Self {
+        DebugMessengerState {
+            suppressed_message_ids,
+            suppressed_message_names,
+            validation_errors: AtomicU64::new(0),
+        }
+    }
+    pub fn validation_errors(&self) -> u64 {
+        self.validation_errors.load(Ordering::Relaxed)
+    }
+    fn is_suppressed(&self, message_id_name: &str, message_id_number: i32) -> bool {
+        self.suppressed_message_ids.contains(&message_id_number)
+            || self
+                .suppressed_message_names
+                .iter()
+                .any(|name| name == message_id_name)
+    }
+
+    /// logs a message and counts it when it is a validation error, returns false when the message
+    /// was suppressed
+    fn handle(
+        &self,
+        message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+        message_id_name: &str,
+        message_id_number: i32,
+        message: &str,
+    ) -> bool {
+        if self.is_suppressed(message_id_name, message_id_number) {
+            return false;
+        }
+        if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+            && message_type.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION)
+        {
+            self.validation_errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let level = log_level(message_severity);
+        log::log!(
+            target: VULKAN_LOG_TARGET,
+            level,
+            "{message_type:?} [{message_id_name} ({message_id_number:#x})] : {message}"
+        );
+        true
+    }
+}
+
+fn log_level(message_severity: vk::DebugUtilsMessageSeverityFlagsEXT) -> log::Level {
+    if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        log::Level::Error
+    } else if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        log::Level::Warn
+    } else if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+        log::Level::Info
+    } else {
+        log::Level::Trace
+    }
+}
+
+/// `p_user_data` must point to a [`DebugMessengerState`]
+pub(crate) unsafe extern "system" fn vulkan_debug_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    p_user_data: *mut std::os::raw::c_void,
+) -> vk::Bool32 {
+    let state = &*(p_user_data as *const DebugMessengerState);
+    let callback_data = *p_callback_data;
+    let message_id_number = callback_data.message_id_number;
+
+    let message_id_name = if callback_data.p_message_id_name.is_null() {
+        Cow::from("")
+    } else {
+        ffi::CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
+    };
+
+    let message = if callback_data.p_message.is_null() {
+        Cow::from("")
+    } else {
+        ffi::CStr::from_ptr(callback_data.p_message).to_string_lossy()
+    };
+
+    state.handle(
+        message_severity,
+        message_type,
+        &message_id_name,
+        message_id_number,
+        &message,
+    );
+
+    vk::FALSE
+}
+
+impl Renderer {
+    /// the number of validation errors reported since the renderer was created, always 0 when
+    /// validation is off
+    pub fn validation_error_count(&self) -> u64 {
+        self.debug_messenger_state.validation_errors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_messenger_counts_validation_errors_test() {
+        let state = DebugMessengerState::new(vec![0x1234], vec!["VUID-suppressed".to_string()]);
+        let error = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR;
+        let validation = vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION;
+
+        assert!(state.handle(error, validation, "VUID-a", 1, "message"));
+        assert!(state.handle(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            validation,
+            "VUID-b",
+            2,
+            "message"
+        ));
+        assert!(state.handle(
+            error,
+            vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+            "VUID-c",
+            3,
+            "message"
+        ));
+        assert!(!state.handle(error, validation, "VUID-d", 0x1234, "message"));
+        assert!(!state.handle(error, validation, "VUID-suppressed", 5, "message"));
+
+        assert_eq!(state.validation_errors(), 1);
+    }
+
+    #[test]
+    fn log_level_test() {
+        assert_eq!(
+            log_level(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR),
+            log::Level::Error
+        );
+        assert_eq!(
+            log_level(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING),
+            log::Level::Warn
+        );
+        assert_eq!(
+            log_level(vk::DebugUtilsMessageSeverityFlagsEXT::INFO),
+            log::Level::Info
+        );
+        assert_eq!(
+            log_level(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE),
+            log::Level::Trace
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::ffi::CStr;
+
+use ash::{vk, Instance};
+
+use super::config::RendererConfig;
+use super::Renderer;
+use crate::core::error::EngineError;
+use crate::core::logging::engine::info;
+
+/// Overrides the device chosen by [`DeviceSelection`], either the index of the device or a part
+/// of its name, e.g. `ENGINE_GPU=1` or `ENGINE_GPU=llvmpipe`
+pub const DEVICE_ENV_VAR: &str = "ENGINE_GPU";
+
+/// How the renderer picks a physical device among the suitable ones
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DeviceSelection {
+    /// the device with the highest score, discrete GPUs are preferred over integrated ones
+    #[default]
+    Auto,
+    /// the first device whose name contains this, case insensitive
+    Name(String),
+    /// the device at this index in [`Renderer::device_candidates`]
+    Index(usize),
+}
+
+impl DeviceSelection {
+    /// reads [`DEVICE_ENV_VAR`], returns `None` when it isn't set or is empty
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(DEVICE_ENV_VAR).ok()?;
+        Self::parse(&value)
+    }
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        Some(match value.parse() {
+            Ok(index) => DeviceSelection::Index(index),
+            Err(_) => DeviceSelection::Name(value.to_string()),
+        })
+    }
+}
+
+/// Optional device features the renderer can require
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceFeature {
+    ShaderClipDistance,
+    FillModeNonSolid,
+    WideLines,
+    SamplerAnisotropy,
+    GeometryShader,
+    TessellationShader,
+    MultiDrawIndirect,
+}
+
+impl DeviceFeature {
+    fn flag(self, features: &mut vk::PhysicalDeviceFeatures) -> &mut vk::Bool32 {
+        match self {
+            DeviceFeature::ShaderClipDistance => &mut features.shader_clip_distance,
+            DeviceFeature::FillModeNonSolid => &mut features.fill_mode_non_solid,
+            DeviceFeature::WideLines => &mut features.wide_lines,
+            DeviceFeature::SamplerAnisotropy => &mut features.sampler_anisotropy,
+            DeviceFeature::GeometryShader => &mut features.geometry_shader,
+            DeviceFeature::TessellationShader => &mut features.tessellation_shader,
+            DeviceFeature::MultiDrawIndirect => &mut features.multi_draw_indirect,
+        }
+    }
+    pub fn is_supported(self, features: &vk::PhysicalDeviceFeatures) -> bool {
+        let mut features = *features;
+        *self.flag(&mut features) == vk::TRUE
+    }
+    /// the features to enable when creating the device
+    pub(crate) fn enabled_features(required: &[DeviceFeature]) -> vk::PhysicalDeviceFeatures {
+        let mut features = vk::PhysicalDeviceFeatures::default();
+        for feature in required {
+            *feature.flag(&mut features) = vk::TRUE;
+        }
+        features
+    }
+}
+
+/// A physical device found while picking one, kept for diagnostics
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub index: usize,
+    pub physical_device: vk::PhysicalDevice,
+    pub name: String,
+    pub properties: vk::PhysicalDeviceProperties,
+    /// a graphics queue family usable by the renderer, `None` when there is none
+    pub queue_family_index: Option<u32>,
+    /// why the device can't be used, `None` when it is suitable
+    pub rejection: Option<String>,
+}
+
+impl DeviceInfo {
+    pub fn is_suitable(&self) -> bool {
+        self.rejection.is_none()
+    }
+    /// higher is better, only used by [`DeviceSelection::Auto`]
+    pub fn score(&self) -> u32 {
+        match self.properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => 4,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+            vk::PhysicalDeviceType::CPU => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// picks the candidate matching `selection`, ties are broken by the order the driver reported
+/// the devices in
+fn select_device(candidates: &[DeviceInfo], selection: &DeviceSelection) -> Result<usize, String> {
+    let mut suitable = candidates
+        .iter()
+        .filter(|candidate| candidate.is_suitable());
+    let chosen = match selection {
+        DeviceSelection::Auto => suitable
+            .rev()
+            .max_by_key(|candidate| candidate.score())
+            .ok_or("no suitable device found".to_string())?,
+        DeviceSelection::Name(name) => suitable
+            .find(|candidate| candidate.name.to_lowercase().contains(&name.to_lowercase()))
+            .ok_or(format!("no suitable device named {:?}", name))?,
+        DeviceSelection::Index(index) => {
+            let candidate = candidates
+                .get(*index)
+                .ok_or(format!("no device at index {}", index))?;
+            if let Some(rejection) = &candidate.rejection {
+                return Err(format!("device {} can't be used: {}", index, rejection));
+            }
+            candidate
+        }
+    };
+    Ok(chosen.index)
+}
+
+impl Renderer {
+    /// lists every physical device and picks one following `config`, or [`DEVICE_ENV_VAR`] when
+    /// set
+    ///
+    /// a device is suitable when it has a graphics queue family accepted by `supports_queue`, and
+    /// supports `config.required_features`, `config.required_extensions` and `extensions`
+    pub(crate) fn pick_physical_device<F>(
+        instance: &Instance,
+        config: &RendererConfig,
+        extensions: &[&CStr],
+        supports_queue: F,
+    ) -> Result<(vk::PhysicalDevice, u32, Vec<DeviceInfo>), EngineError>
+    where
+        F: Fn(vk::PhysicalDevice, u32) -> bool,
+    {
+        let pdevices = unsafe { instance.enumerate_physical_devices()? };
+        let candidates: Vec<DeviceInfo> = pdevices
+            .iter()
+            .enumerate()
+            .map(|(index, &pdevice)| {
+                Self::describe_device(
+                    instance,
+                    config,
+                    extensions,
+                    index,
+                    pdevice,
+                    &supports_queue,
+                )
+            })
+            .collect();
+
+        let selection = DeviceSelection::from_env().unwrap_or_else(|| config.device.clone());
+        let chosen = select_device(&candidates, &selection).map_err(|e| {
+            let names: Vec<String> = candidates
+                .iter()
+                .map(|candidate| match &candidate.rejection {
+                    None => format!("{}: {}", candidate.index, candidate.name),
+                    Some(rejection) => {
+                        format!("{}: {} ({})", candidate.index, candidate.name, rejection)
+                    }
+                })
+                .collect();
+            EngineError::NoSuitableDevice(format!("{}, devices: [{}]", e, names.join(", ")))
+        })?;
+
+        let candidate = &candidates[chosen];
+        info!(
+            "using device {}: {} ({:?})",
+            candidate.index, candidate.name, candidate.properties.device_type
+        );
+        let (pdevice, queue_family_index) = (
+            candidate.physical_device,
+            candidate.queue_family_index.unwrap(),
+        );
+        Ok((pdevice, queue_family_index, candidates))
+    }
+
+    fn describe_device<F>(
+        instance: &Instance,
+        config: &RendererConfig,
+        extensions: &[&CStr],
+        index: usize,
+        pdevice: vk::PhysicalDevice,
+        supports_queue: &F,
+    ) -> DeviceInfo
+    where
+        F: Fn(vk::PhysicalDevice, u32) -> bool,
+    {
+        unsafe {
+            let properties = instance.get_physical_device_properties(pdevice);
+            let name = properties
+                .device_name_as_c_str()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let queue_family_index = instance
+                .get_physical_device_queue_family_properties(pdevice)
+                .iter()
+                .enumerate()
+                .position(|(index, info)| {
+                    info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                        && supports_queue(pdevice, index as u32)
+                })
+                .map(|index| index as u32);
+
+            let features = instance.get_physical_device_features(pdevice);
+            let available_extensions = instance
+                .enumerate_device_extension_properties(pdevice)
+                .unwrap_or_default();
+
+            let missing_feature = config
+                .required_features
+                .iter()
+                .find(|feature| !feature.is_supported(&features));
+            let missing_extension =
+                config
+                    .required_extensions
+                    .iter()
+                    .chain(extensions)
+                    .find(|&&extension| {
+                        !available_extensions
+                            .iter()
+                            .any(|available| available.extension_name_as_c_str() == Ok(extension))
+                    });
+
+            let rejection = if queue_family_index.is_none() {
+                Some("no usable graphics queue".to_string())
+            } else if let Some(feature) = missing_feature {
+                Some(format!("missing feature {:?}", feature))
+            } else {
+                missing_extension.map(|extension| format!("missing extension {:?}", extension))
+            };
+
+            DeviceInfo {
+                index,
+                physical_device: pdevice,
+                name,
+                properties,
+                queue_family_index,
+                rejection,
+            }
+        }
+    }
+
+    /// the properties of the device the renderer uses
+    pub fn device_properties(&self) -> &vk::PhysicalDeviceProperties {
+        &self.device_candidates[self.device_index].properties
+    }
+    /// every physical device that was considered, including the unsuitable ones
+    pub fn device_candidates(&self) -> &[DeviceInfo] {
+        &self.device_candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(index: usize, name: &str, device_type: vk::PhysicalDeviceType) -> DeviceInfo {
+        DeviceInfo {
+            index,
+            physical_device: vk::PhysicalDevice::null(),
+            name: name.to_string(),
+            properties: vk::PhysicalDeviceProperties {
+                device_type,
+                ..Default::default()
+            },
+            queue_family_index: Some(0),
+            rejection: None,
+        }
+    }
+
+    #[test]
+    fn select_device_test() {
+        let mut candidates = vec![
+            candidate(0, "llvmpipe", vk::PhysicalDeviceType::CPU),
+            candidate(1, "Intel UHD", vk::PhysicalDeviceType::INTEGRATED_GPU),
+            candidate(2, "NVIDIA RTX", vk::PhysicalDeviceType::DISCRETE_GPU),
+        ];
+
+        assert_eq!(select_device(&candidates, &DeviceSelection::Auto), Ok(2));
+        assert_eq!(
+            select_device(&candidates, &DeviceSelection::Name("intel".into())),
+            Ok(1)
+        );
+        assert_eq!(
+            select_device(&candidates, &DeviceSelection::Index(0)),
+            Ok(0)
+        );
+        assert!(select_device(&candidates, &DeviceSelection::Index(3)).is_err());
+
+        candidates[2].rejection = Some("missing feature".to_string());
+        assert_eq!(select_device(&candidates, &DeviceSelection::Auto), Ok(1));
+        assert!(select_device(&candidates, &DeviceSelection::Index(2)).is_err());
+    }
+
+    #[test]
+    fn device_selection_parse_test() {
+        assert_eq!(DeviceSelection::parse("1"), Some(DeviceSelection::Index(1)));
+        assert_eq!(
+            DeviceSelection::parse("lavapipe"),
+            Some(DeviceSelection::Name("lavapipe".to_string()))
+        );
+        assert_eq!(DeviceSelection::parse(" "), None);
+    }
+}
@@ -0,0 +1,81 @@
+use ash::{vk, Device};
+
+use super::Renderer;
+
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+/// The resources used to record and submit a single frame, the renderer keeps one per frame in
+/// flight so the CPU can record a frame while the GPU is still executing the previous ones
+pub struct FrameData {
+    pub command_buffer: vk::CommandBuffer,
+    pub present_complete_semaphore: vk::Semaphore,
+    /// signaled once the GPU has finished executing `command_buffer`
+    pub in_flight_fence: vk::Fence,
+}
+
+impl FrameData {
+    pub(crate) fn new(device: &Device, pool: vk::CommandPool) -> Result<Self, vk::Result> {
+        unsafe {
+            let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
+                .command_buffer_count(1)
+                .command_pool(pool)
+                .level(vk::CommandBufferLevel::PRIMARY);
+            let command_buffer = device.allocate_command_buffers(&command_buffer_allocate_info)?[0];
+
+            let semaphore_create_info = vk::SemaphoreCreateInfo::default();
+            let present_complete_semaphore =
+                device.create_semaphore(&semaphore_create_info, None)?;
+
+            let fence_create_info =
+                vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
+            let in_flight_fence = device.create_fence(&fence_create_info, None)?;
+
+            Ok(FrameData {
+                command_buffer,
+                present_complete_semaphore,
+                in_flight_fence,
+            })
+        }
+    }
+
+    /// creates the resources for `count` frames in flight
+    pub(crate) fn create_frames(
+        device: &Device,
+        pool: vk::CommandPool,
+        count: usize,
+    ) -> Result<Vec<Self>, vk::Result> {
+        (0..count.max(1))
+            .map(|_| FrameData::new(device, pool))
+            .collect()
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device, pool: vk::CommandPool) {
+        device.destroy_semaphore(self.present_complete_semaphore, None);
+        device.destroy_fence(self.in_flight_fence, None);
+        device.free_command_buffers(pool, &[self.command_buffer]);
+    }
+}
+
+impl Renderer {
+    /// the number of frames that can be recorded before waiting on the GPU
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.len()
+    }
+    /// changes the number of frames in flight, waits for the GPU to go idle and recreates the
+    /// per frame resources
+    pub fn set_frames_in_flight(&mut self, count: usize) -> Result<(), vk::Result> {
+        if count.max(1) == self.frames.len() {
+            return Ok(());
+        }
+        unsafe {
+            self.device.device_wait_idle()?;
+            // they wait on the fences of the frames destroyed here
+            self.destroy_retired_meshes();
+            for mut frame in self.frames.drain(..) {
+                frame.destroy(&self.device, self.pool);
+            }
+        }
+        self.frames = FrameData::create_frames(&self.device, self.pool, count)?;
+        Ok(())
+    }
+}
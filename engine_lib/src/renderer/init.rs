@@ -1,6 +1,4 @@
-use std::borrow::Cow;
-use std::error::Error;
-use std::ffi;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::sync::Arc;
 
@@ -11,32 +9,119 @@ use ash::{
 };
 use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
-use super::Renderer;
+use super::config::{RendererConfig, VALIDATION_LAYER_NAME};
+use super::debug::{vulkan_debug_callback, DebugMessengerState};
+use super::device::{DeviceFeature, DeviceInfo};
+use super::frames::FrameData;
+use super::pipeline::{create_render_pass, MeshPipeline};
+use super::swapchain::SwapchainTarget;
+use super::{RenderTarget, Renderer};
+use crate::core::error::EngineError;
+use crate::core::logging::engine::error;
+
+pub(crate) const DEPTH_FORMAT: vk::Format = vk::Format::D16_UNORM;
+
+/// the instance, the debug utils loader, the debug messenger and its user data
+pub(crate) type InstanceParts = (
+    Entry,
+    Instance,
+    debug_utils::Instance,
+    vk::DebugUtilsMessengerEXT,
+    Box<DebugMessengerState>,
+);
 
 impl Renderer {
-    pub fn init(window: Arc<winit::window::Window>) -> Result<Self, Box<dyn Error>> {
+    pub fn init(
+        window: Arc<winit::window::Window>,
+        config: &RendererConfig,
+    ) -> Result<Self, EngineError> {
+        let extension_names =
+            ash_window::enumerate_required_extensions(window.display_handle()?.as_raw())?;
+        let (entry, instance, debug_utils_loader, debug_call_back, debug_messenger_state) =
+            Self::create_instance(config, extension_names)?;
+
         unsafe {
-            let entry = Entry::linked();
-            let app_name = c"VulkanTriangle";
+            let surface = ash_window::create_surface(
+                &entry,
+                &instance,
+                window.display_handle()?.as_raw(),
+                window.window_handle()?.as_raw(),
+                None,
+            )?;
+            let surface_loader = surface::Instance::new(&entry, &instance);
+            let (pdevice, queue_family_index, device_candidates) = Self::pick_physical_device(
+                &instance,
+                config,
+                &[swapchain::NAME],
+                |pdevice, index| {
+                    surface_loader
+                        .get_physical_device_surface_support(pdevice, index, surface)
+                        .unwrap_or(false)
+                },
+            )?;
+            let (device, present_queue) = Self::create_device(
+                &instance,
+                config,
+                pdevice,
+                queue_family_index,
+                &[swapchain::NAME],
+            )?;
 
-            let layer_names = [c"VK_LAYER_KHRONOS_validation"];
-            let layers_names_raw: Vec<*const c_char> = layer_names
-                .iter()
-                .map(|raw_name| raw_name.as_ptr())
-                .collect();
+            let target = SwapchainTarget::new(
+                &instance,
+                &device,
+                pdevice,
+                window,
+                surface_loader,
+                surface,
+                config.present_mode,
+            )?;
+
+            Self::from_parts(
+                entry,
+                instance,
+                debug_utils_loader,
+                debug_call_back,
+                debug_messenger_state,
+                pdevice,
+                device_candidates,
+                queue_family_index,
+                device,
+                present_queue,
+                RenderTarget::Swapchain(target),
+                config,
+            )
+        }
+    }
 
-            let mut extension_names =
-                ash_window::enumerate_required_extensions(window.display_handle()?.as_raw())
-                    .unwrap()
-                    .to_vec();
-            extension_names.push(debug_utils::NAME.as_ptr());
+    /// creates the vulkan instance described by `config`, `extension_names` are enabled on top
+    /// of `debug_utils` when validation is on
+    ///
+    /// the debug messenger is null when validation is off, otherwise it logs under
+    /// [`super::VULKAN_LOG_TARGET`]
+    pub(crate) fn create_instance(
+        config: &RendererConfig,
+        extension_names: &[*const c_char],
+    ) -> Result<InstanceParts, EngineError> {
+        unsafe {
+            let entry = Entry::linked();
+            let app_name = CString::new(config.app_name.as_str())?;
+            let engine_name = CString::new(config.engine_name.as_str())?;
+            let validation = config.validation_supported(&entry);
+
+            let mut layers_names_raw: Vec<*const c_char> = vec![];
+            let mut extension_names = extension_names.to_vec();
+            if validation {
+                layers_names_raw.push(VALIDATION_LAYER_NAME.as_ptr());
+                extension_names.push(debug_utils::NAME.as_ptr());
+            }
 
             let appinfo = vk::ApplicationInfo::default()
-                .application_name(app_name)
-                .application_version(0)
-                .engine_name(app_name)
-                .engine_version(0)
-                .api_version(vk::make_api_version(0, 1, 0, 0));
+                .application_name(&app_name)
+                .application_version(config.app_version)
+                .engine_name(&engine_name)
+                .engine_version(config.engine_version)
+                .api_version(config.api_version);
 
             let create_flags = vk::InstanceCreateFlags::default();
 
@@ -46,70 +131,68 @@ impl Renderer {
                 .enabled_extension_names(&extension_names)
                 .flags(create_flags);
 
-            let instance: Instance = entry
-                .create_instance(&create_info, None)
-                .expect("Instance creation error");
+            let instance: Instance = entry.create_instance(&create_info, None)?;
+            let debug_utils_loader = debug_utils::Instance::new(&entry, &instance);
+            let debug_messenger_state = Box::new(DebugMessengerState::new(
+                config.suppressed_message_ids.clone(),
+                config.suppressed_message_names.clone(),
+            ));
+            if !validation {
+                return Ok((
+                    entry,
+                    instance,
+                    debug_utils_loader,
+                    vk::DebugUtilsMessengerEXT::null(),
+                    debug_messenger_state,
+                ));
+            }
 
             let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::default()
                 .message_severity(
                     vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
                         | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                        | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+                        | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
+                        | vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
                 )
                 .message_type(
                     vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
                         | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
                         | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
                 )
-                .pfn_user_callback(Some(vulkan_debug_callback));
+                .pfn_user_callback(Some(vulkan_debug_callback))
+                .user_data(debug_messenger_state.as_ref() as *const DebugMessengerState
+                    as *mut std::os::raw::c_void);
 
-            let debug_utils_loader = debug_utils::Instance::new(&entry, &instance);
-            let debug_call_back = debug_utils_loader
-                .create_debug_utils_messenger(&debug_info, None)
-                .unwrap();
-            let surface = ash_window::create_surface(
-                &entry,
-                &instance,
-                window.display_handle()?.as_raw(),
-                window.window_handle()?.as_raw(),
-                None,
-            )
-            .unwrap();
-            let pdevices = instance
-                .enumerate_physical_devices()
-                .expect("Physical device error");
-            let surface_loader = surface::Instance::new(&entry, &instance);
-            let (pdevice, queue_family_index) = pdevices
+            let debug_call_back =
+                debug_utils_loader.create_debug_utils_messenger(&debug_info, None)?;
+
+            Ok((
+                entry,
+                instance,
+                debug_utils_loader,
+                debug_call_back,
+                debug_messenger_state,
+            ))
+        }
+    }
+
+    /// creates the device with the features and extensions required by `config`, `extensions`
+    /// are enabled on top of them
+    pub(crate) fn create_device(
+        instance: &Instance,
+        config: &RendererConfig,
+        pdevice: vk::PhysicalDevice,
+        queue_family_index: u32,
+        extensions: &[&CStr],
+    ) -> Result<(Device, vk::Queue), EngineError> {
+        unsafe {
+            let features = DeviceFeature::enabled_features(&config.required_features);
+            let device_extension_names_raw: Vec<*const c_char> = config
+                .required_extensions
                 .iter()
-                .find_map(|pdevice| {
-                    instance
-                        .get_physical_device_queue_family_properties(*pdevice)
-                        .iter()
-                        .enumerate()
-                        .find_map(|(index, info)| {
-                            let supports_graphic_and_surface =
-                                info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
-                                    && surface_loader
-                                        .get_physical_device_surface_support(
-                                            *pdevice,
-                                            index as u32,
-                                            surface,
-                                        )
-                                        .unwrap();
-                            if supports_graphic_and_surface {
-                                Some((*pdevice, index))
-                            } else {
-                                None
-                            }
-                        })
-                })
-                .expect("Couldn't find suitable device.");
-            let queue_family_index = queue_family_index as u32;
-            let device_extension_names_raw = [swapchain::NAME.as_ptr()];
-            let features = vk::PhysicalDeviceFeatures {
-                shader_clip_distance: 1,
-                ..Default::default()
-            };
+                .chain(extensions)
+                .map(|extension| extension.as_ptr())
+                .collect();
             let priorities = [1.0];
 
             let queue_info = vk::DeviceQueueCreateInfo::default()
@@ -121,211 +204,60 @@ impl Renderer {
                 .enabled_extension_names(&device_extension_names_raw)
                 .enabled_features(&features);
 
-            let device: Device = instance
-                .create_device(pdevice, &device_create_info, None)
-                .unwrap();
+            let device: Device = instance.create_device(pdevice, &device_create_info, None)?;
 
             let present_queue = device.get_device_queue(queue_family_index, 0);
 
-            let surface_format = surface_loader
-                .get_physical_device_surface_formats(pdevice, surface)
-                .unwrap()[0];
-
-            let surface_capabilities = surface_loader
-                .get_physical_device_surface_capabilities(pdevice, surface)
-                .unwrap();
-            let mut desired_image_count = surface_capabilities.min_image_count + 1;
-            if surface_capabilities.max_image_count > 0
-                && desired_image_count > surface_capabilities.max_image_count
-            {
-                desired_image_count = surface_capabilities.max_image_count;
-            }
-            let surface_resolution = match surface_capabilities.current_extent.width {
-                u32::MAX => vk::Extent2D {
-                    width: window.inner_size().width,
-                    height: window.inner_size().height,
-                },
-                _ => surface_capabilities.current_extent,
-            };
-            let pre_transform = if surface_capabilities
-                .supported_transforms
-                .contains(vk::SurfaceTransformFlagsKHR::IDENTITY)
-            {
-                vk::SurfaceTransformFlagsKHR::IDENTITY
-            } else {
-                surface_capabilities.current_transform
-            };
-            let present_modes = surface_loader
-                .get_physical_device_surface_present_modes(pdevice, surface)
-                .unwrap();
-            let present_mode = present_modes
-                .iter()
-                .cloned()
-                .find(|&mode| mode == vk::PresentModeKHR::MAILBOX)
-                .unwrap_or(vk::PresentModeKHR::FIFO);
-            let swapchain_loader = swapchain::Device::new(&instance, &device);
-
-            let swapchain_create_info = vk::SwapchainCreateInfoKHR::default()
-                .surface(surface)
-                .min_image_count(desired_image_count)
-                .image_color_space(surface_format.color_space)
-                .image_format(surface_format.format)
-                .image_extent(surface_resolution)
-                .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
-                .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
-                .pre_transform(pre_transform)
-                .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
-                .present_mode(present_mode)
-                .clipped(true)
-                .image_array_layers(1);
-
-            let swapchain = swapchain_loader
-                .create_swapchain(&swapchain_create_info, None)
-                .unwrap();
+            Ok((device, present_queue))
+        }
+    }
 
+    /// creates the resources shared by every render target
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_parts(
+        entry: Entry,
+        instance: Instance,
+        debug_utils_loader: debug_utils::Instance,
+        debug_call_back: vk::DebugUtilsMessengerEXT,
+        debug_messenger_state: Box<DebugMessengerState>,
+        pdevice: vk::PhysicalDevice,
+        device_candidates: Vec<DeviceInfo>,
+        queue_family_index: u32,
+        device: Device,
+        present_queue: vk::Queue,
+        target: RenderTarget,
+        config: &RendererConfig,
+    ) -> Result<Self, EngineError> {
+        unsafe {
             let pool_create_info = vk::CommandPoolCreateInfo::default()
                 .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
                 .queue_family_index(queue_family_index);
 
-            let pool = device.create_command_pool(&pool_create_info, None).unwrap();
+            let pool = device.create_command_pool(&pool_create_info, None)?;
 
             let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
-                .command_buffer_count(2)
+                .command_buffer_count(1)
                 .command_pool(pool)
                 .level(vk::CommandBufferLevel::PRIMARY);
 
-            let command_buffers = device
-                .allocate_command_buffers(&command_buffer_allocate_info)
-                .unwrap();
+            let command_buffers = device.allocate_command_buffers(&command_buffer_allocate_info)?;
             let setup_command_buffer = command_buffers[0];
-            let draw_command_buffer = command_buffers[1];
+            let frames = FrameData::create_frames(&device, pool, config.frames_in_flight)?;
 
-            let present_images = swapchain_loader.get_swapchain_images(swapchain).unwrap();
-            let present_image_views: Vec<vk::ImageView> = present_images
-                .iter()
-                .map(|&image| {
-                    let create_view_info = vk::ImageViewCreateInfo::default()
-                        .view_type(vk::ImageViewType::TYPE_2D)
-                        .format(surface_format.format)
-                        .components(vk::ComponentMapping {
-                            r: vk::ComponentSwizzle::R,
-                            g: vk::ComponentSwizzle::G,
-                            b: vk::ComponentSwizzle::B,
-                            a: vk::ComponentSwizzle::A,
-                        })
-                        .subresource_range(vk::ImageSubresourceRange {
-                            aspect_mask: vk::ImageAspectFlags::COLOR,
-                            base_mip_level: 0,
-                            level_count: 1,
-                            base_array_layer: 0,
-                            layer_count: 1,
-                        })
-                        .image(image);
-                    device.create_image_view(&create_view_info, None).unwrap()
-                })
-                .collect();
             let device_memory_properties = instance.get_physical_device_memory_properties(pdevice);
-            let depth_image_create_info = vk::ImageCreateInfo::default()
-                .image_type(vk::ImageType::TYPE_2D)
-                .format(vk::Format::D16_UNORM)
-                .extent(surface_resolution.into())
-                .mip_levels(1)
-                .array_layers(1)
-                .samples(vk::SampleCountFlags::TYPE_1)
-                .tiling(vk::ImageTiling::OPTIMAL)
-                .usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)
-                .sharing_mode(vk::SharingMode::EXCLUSIVE);
-
-            let depth_image = device.create_image(&depth_image_create_info, None).unwrap();
-            let depth_image_memory_req = device.get_image_memory_requirements(depth_image);
-            let depth_image_memory_index = find_memorytype_index(
-                &depth_image_memory_req,
-                &device_memory_properties,
-                vk::MemoryPropertyFlags::DEVICE_LOCAL,
-            )
-            .expect("Unable to find suitable memory index for depth image.");
-
-            let depth_image_allocate_info = vk::MemoryAllocateInfo::default()
-                .allocation_size(depth_image_memory_req.size)
-                .memory_type_index(depth_image_memory_index);
-
-            let depth_image_memory = device
-                .allocate_memory(&depth_image_allocate_info, None)
-                .unwrap();
-
-            device
-                .bind_image_memory(depth_image, depth_image_memory, 0)
-                .expect("Unable to bind depth image memory");
+            let (depth_image, depth_image_memory, depth_image_view) =
+                create_depth_image(&device, &device_memory_properties, target.extent())?;
 
             let fence_create_info =
                 vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
 
-            let draw_commands_reuse_fence = device
-                .create_fence(&fence_create_info, None)
-                .expect("Create fence failed.");
-            let setup_commands_reuse_fence = device
-                .create_fence(&fence_create_info, None)
-                .expect("Create fence failed.");
+            let setup_commands_reuse_fence = device.create_fence(&fence_create_info, None)?;
 
-            record_submit_commandbuffer(
-                &device,
-                setup_command_buffer,
-                setup_commands_reuse_fence,
-                present_queue,
-                &[],
-                &[],
-                &[],
-                |device, setup_command_buffer| {
-                    let layout_transition_barriers = vk::ImageMemoryBarrier::default()
-                        .image(depth_image)
-                        .dst_access_mask(
-                            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
-                                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-                        )
-                        .new_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
-                        .old_layout(vk::ImageLayout::UNDEFINED)
-                        .subresource_range(
-                            vk::ImageSubresourceRange::default()
-                                .aspect_mask(vk::ImageAspectFlags::DEPTH)
-                                .layer_count(1)
-                                .level_count(1),
-                        );
-
-                    device.cmd_pipeline_barrier(
-                        setup_command_buffer,
-                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
-                        vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
-                        vk::DependencyFlags::empty(),
-                        &[],
-                        &[],
-                        &[layout_transition_barriers],
-                    );
-                },
-            );
-
-            let depth_image_view_info = vk::ImageViewCreateInfo::default()
-                .subresource_range(
-                    vk::ImageSubresourceRange::default()
-                        .aspect_mask(vk::ImageAspectFlags::DEPTH)
-                        .level_count(1)
-                        .layer_count(1),
-                )
-                .image(depth_image)
-                .format(depth_image_create_info.format)
-                .view_type(vk::ImageViewType::TYPE_2D);
-
-            let depth_image_view = device
-                .create_image_view(&depth_image_view_info, None)
-                .unwrap();
-
-            let semaphore_create_info = vk::SemaphoreCreateInfo::default();
-
-            let present_complete_semaphore = device
-                .create_semaphore(&semaphore_create_info, None)
-                .unwrap();
-            let rendering_complete_semaphore = device
-                .create_semaphore(&semaphore_create_info, None)
-                .unwrap();
+            let render_pass =
+                create_render_pass(&device, target.color_format(), target.final_layout())?;
+            let mesh_pipeline = MeshPipeline::new(&device, render_pass)?;
+            let framebuffers =
+                create_framebuffers(&device, render_pass, &target, depth_image_view)?;
 
             Ok(Self {
                 entry,
@@ -333,61 +265,114 @@ impl Renderer {
                 device,
                 queue_family_index,
                 pdevice,
+                device_index: device_candidates
+                    .iter()
+                    .position(|candidate| candidate.physical_device == pdevice)
+                    .unwrap_or(0),
+                device_candidates,
                 device_memory_properties,
-                window,
-                surface_loader,
-                surface_format,
                 present_queue,
-                surface_resolution,
-                swapchain_loader,
-                swapchain,
-                present_images,
-                present_image_views,
+                target,
                 pool,
-                draw_command_buffer,
                 setup_command_buffer,
+                frames,
                 depth_image,
                 depth_image_view,
-                present_complete_semaphore,
-                rendering_complete_semaphore,
-                draw_commands_reuse_fence,
                 setup_commands_reuse_fence,
-                surface,
                 debug_call_back,
+                debug_messenger_state,
                 debug_utils_loader,
                 depth_image_memory,
+                render_pass,
+                framebuffers,
+                mesh_pipeline,
+                clear_color: [0.0, 0.0, 0.0, 1.0],
+                swapchain_out_of_date: false,
+                meshes: vec![],
+                draw_calls: vec![],
+                retired_meshes: vec![],
             })
         }
     }
 }
 
-unsafe extern "system" fn vulkan_debug_callback(
-    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
-    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
-    _user_data: *mut std::os::raw::c_void,
-) -> vk::Bool32 {
-    let callback_data = *p_callback_data;
-    let message_id_number = callback_data.message_id_number;
-
-    let message_id_name = if callback_data.p_message_id_name.is_null() {
-        Cow::from("")
-    } else {
-        ffi::CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
-    };
-
-    let message = if callback_data.p_message.is_null() {
-        Cow::from("")
-    } else {
-        ffi::CStr::from_ptr(callback_data.p_message).to_string_lossy()
-    };
-
-    println!(
-        "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
-    );
-
-    vk::FALSE
+/// creates the depth attachment, its layout is left undefined as the render pass clears it
+pub(crate) fn create_depth_image(
+    device: &Device,
+    device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    extent: vk::Extent2D,
+) -> Result<(vk::Image, vk::DeviceMemory, vk::ImageView), vk::Result> {
+    unsafe {
+        let depth_image_create_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(DEPTH_FORMAT)
+            .extent(extent.into())
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let depth_image = device.create_image(&depth_image_create_info, None)?;
+        let depth_image_memory_req = device.get_image_memory_requirements(depth_image);
+        let depth_image_memory_index = find_memorytype_index(
+            &depth_image_memory_req,
+            device_memory_properties,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )
+        .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+
+        let depth_image_allocate_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(depth_image_memory_req.size)
+            .memory_type_index(depth_image_memory_index);
+
+        let depth_image_memory = device.allocate_memory(&depth_image_allocate_info, None)?;
+
+        device.bind_image_memory(depth_image, depth_image_memory, 0)?;
+
+        let depth_image_view_info = vk::ImageViewCreateInfo::default()
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(vk::ImageAspectFlags::DEPTH)
+                    .level_count(1)
+                    .layer_count(1),
+            )
+            .image(depth_image)
+            .format(DEPTH_FORMAT)
+            .view_type(vk::ImageViewType::TYPE_2D);
+
+        let depth_image_view = device.create_image_view(&depth_image_view_info, None)?;
+
+        Ok((depth_image, depth_image_memory, depth_image_view))
+    }
+}
+
+/// creates a framebuffer for each color image view of `target`, all sharing the depth image
+pub(crate) fn create_framebuffers(
+    device: &Device,
+    render_pass: vk::RenderPass,
+    target: &RenderTarget,
+    depth_image_view: vk::ImageView,
+) -> Result<Vec<vk::Framebuffer>, vk::Result> {
+    let extent = target.extent();
+    target
+        .color_image_views()
+        .iter()
+        .map(|&color_image_view| {
+            let framebuffer_attachments = [color_image_view, depth_image_view];
+            let frame_buffer_create_info = vk::FramebufferCreateInfo::default()
+                .render_pass(render_pass)
+                .attachments(&framebuffer_attachments)
+                .width(extent.width)
+                .height(extent.height)
+                .layers(1);
+
+            unsafe { device.create_framebuffer(&frame_buffer_create_info, None) }
+        })
+        .collect()
 }
+
 pub fn find_memorytype_index(
     memory_req: &vk::MemoryRequirements,
     memory_prop: &vk::PhysicalDeviceMemoryProperties,
@@ -402,6 +387,45 @@ pub fn find_memorytype_index(
         })
         .map(|(index, _memory_type)| index as _)
 }
+/// creates a buffer bound to new memory with `flags`, nothing is left behind when it fails
+pub(crate) fn create_buffer(
+    device: &Device,
+    device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    flags: vk::MemoryPropertyFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory), EngineError> {
+    unsafe {
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = device.create_buffer(&buffer_info, None)?;
+        let buffer_memory = (|| {
+            let buffer_memory_req = device.get_buffer_memory_requirements(buffer);
+            let buffer_memory_index =
+                find_memorytype_index(&buffer_memory_req, device_memory_properties, flags)
+                    .ok_or(EngineError::NoSuitableMemoryType)?;
+            let allocate_info = vk::MemoryAllocateInfo::default()
+                .allocation_size(buffer_memory_req.size)
+                .memory_type_index(buffer_memory_index);
+            let buffer_memory = device.allocate_memory(&allocate_info, None)?;
+            if let Err(e) = device.bind_buffer_memory(buffer, buffer_memory, 0) {
+                device.free_memory(buffer_memory, None);
+                return Err(e.into());
+            }
+            Ok(buffer_memory)
+        })();
+        match buffer_memory {
+            Ok(buffer_memory) => Ok((buffer, buffer_memory)),
+            Err(e) => {
+                device.destroy_buffer(buffer, None);
+                Err(e)
+            }
+        }
+    }
+}
+#[allow(clippy::too_many_arguments)]
 pub fn record_submit_commandbuffer<F: FnOnce(&Device, vk::CommandBuffer)>(
     device: &Device,
     command_buffer: vk::CommandBuffer,
@@ -411,33 +435,23 @@ pub fn record_submit_commandbuffer<F: FnOnce(&Device, vk::CommandBuffer)>(
     wait_semaphores: &[vk::Semaphore],
     signal_semaphores: &[vk::Semaphore],
     f: F,
-) {
+) -> Result<(), vk::Result> {
     unsafe {
-        device
-            .wait_for_fences(&[command_buffer_reuse_fence], true, u64::MAX)
-            .expect("Wait for fence failed.");
-
-        device
-            .reset_fences(&[command_buffer_reuse_fence])
-            .expect("Reset fences failed.");
-
-        device
-            .reset_command_buffer(
-                command_buffer,
-                vk::CommandBufferResetFlags::RELEASE_RESOURCES,
-            )
-            .expect("Reset command buffer failed.");
+        device.wait_for_fences(&[command_buffer_reuse_fence], true, u64::MAX)?;
+
+        device.reset_fences(&[command_buffer_reuse_fence])?;
+
+        device.reset_command_buffer(
+            command_buffer,
+            vk::CommandBufferResetFlags::RELEASE_RESOURCES,
+        )?;
 
         let command_buffer_begin_info = vk::CommandBufferBeginInfo::default()
             .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
 
-        device
-            .begin_command_buffer(command_buffer, &command_buffer_begin_info)
-            .expect("Begin commandbuffer");
+        device.begin_command_buffer(command_buffer, &command_buffer_begin_info)?;
         f(device, command_buffer);
-        device
-            .end_command_buffer(command_buffer)
-            .expect("End commandbuffer");
+        device.end_command_buffer(command_buffer)?;
 
         let command_buffers = vec![command_buffer];
 
@@ -447,36 +461,49 @@ pub fn record_submit_commandbuffer<F: FnOnce(&Device, vk::CommandBuffer)>(
             .command_buffers(&command_buffers)
             .signal_semaphores(signal_semaphores);
 
-        device
-            .queue_submit(submit_queue, &[submit_info], command_buffer_reuse_fence)
-            .expect("queue submit failed.");
+        device.queue_submit(submit_queue, &[submit_info], command_buffer_reuse_fence)?;
     }
+    Ok(())
 }
 impl Drop for Renderer {
     fn drop(&mut self) {
         unsafe {
-            self.device.device_wait_idle().unwrap();
-            self.device
-                .destroy_semaphore(self.present_complete_semaphore, None);
-            self.device
-                .destroy_semaphore(self.rendering_complete_semaphore, None);
-            self.device
-                .destroy_fence(self.draw_commands_reuse_fence, None);
+            if let Err(e) = self.device.device_wait_idle() {
+                error!("failed to wait for the device before destroying it: {e}");
+            }
+            for mesh in self.meshes.iter_mut().flatten() {
+                mesh.destroy(&self.device);
+            }
+            self.destroy_retired_meshes();
+            self.mesh_pipeline.destroy(&self.device);
+            for &framebuffer in self.framebuffers.iter() {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+            self.device.destroy_render_pass(self.render_pass, None);
+            for frame in self.frames.iter_mut() {
+                frame.destroy(&self.device, self.pool);
+            }
             self.device
                 .destroy_fence(self.setup_commands_reuse_fence, None);
             self.device.free_memory(self.depth_image_memory, None);
             self.device.destroy_image_view(self.depth_image_view, None);
             self.device.destroy_image(self.depth_image, None);
-            for &image_view in self.present_image_views.iter() {
-                self.device.destroy_image_view(image_view, None);
-            }
             self.device.destroy_command_pool(self.pool, None);
-            self.swapchain_loader
-                .destroy_swapchain(self.swapchain, None);
+            match &mut self.target {
+                RenderTarget::Swapchain(target) => {
+                    target.destroy_swapchain(&self.device);
+                    target.destroy_semaphores(&self.device);
+                }
+                RenderTarget::Offscreen(target) => target.destroy(&self.device),
+            }
             self.device.destroy_device(None);
-            self.surface_loader.destroy_surface(self.surface, None);
-            self.debug_utils_loader
-                .destroy_debug_utils_messenger(self.debug_call_back, None);
+            if let RenderTarget::Swapchain(target) = &mut self.target {
+                target.destroy_surface();
+            }
+            if self.debug_call_back != vk::DebugUtilsMessengerEXT::null() {
+                self.debug_utils_loader
+                    .destroy_debug_utils_messenger(self.debug_call_back, None);
+            }
             self.instance.destroy_instance(None);
         }
     }
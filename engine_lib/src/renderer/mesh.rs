@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use ash::{vk, Device};
+
+use super::init::create_buffer;
+use super::Renderer;
+use crate::core::datatypes::matrices::Matrix;
+use crate::core::datatypes::meshes::Mesh;
+use crate::core::error::EngineError;
+
+/// Refers to a mesh uploaded with [`Renderer::upload_mesh`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MeshHandle(pub(crate) usize);
+
+/// A mesh stored in GPU vertex and index buffers
+pub struct GpuMesh {
+    pub vertex_buffer: vk::Buffer,
+    pub vertex_buffer_memory: vk::DeviceMemory,
+    pub index_buffer: vk::Buffer,
+    pub index_buffer_memory: vk::DeviceMemory,
+    pub index_count: u32,
+}
+
+/// A freed mesh whose buffers may still be used by frames in flight
+pub(crate) struct RetiredMesh {
+    mesh: GpuMesh,
+    /// the fences of the frames that were in flight when the mesh was freed, the mesh is
+    /// destroyed once each of them was waited on
+    fences: Vec<vk::Fence>,
+}
+
+/// A request to draw a mesh in the next frame
+pub struct DrawCall {
+    pub mesh: MeshHandle,
+    pub transform: Matrix<4, 4>,
+    pub color: [f32; 4],
+}
+
+impl GpuMesh {
+    fn new(
+        device: &Device,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        mesh: &Mesh,
+    ) -> Result<Self, EngineError> {
+        let (vertices, indices) = mesh_buffers(mesh);
+        if indices.is_empty() {
+            return Err(EngineError::EmptyMesh);
+        }
+
+        let (vertex_buffer, vertex_buffer_memory) = create_host_buffer(
+            device,
+            device_memory_properties,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+            &vertices,
+        )?;
+        let (index_buffer, index_buffer_memory) = match create_host_buffer(
+            device,
+            device_memory_properties,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+            &indices,
+        ) {
+            Ok(index_buffer) => index_buffer,
+            Err(e) => {
+                unsafe {
+                    device.destroy_buffer(vertex_buffer, None);
+                    device.free_memory(vertex_buffer_memory, None);
+                }
+                return Err(e);
+            }
+        };
+
+        Ok(GpuMesh {
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+            index_count: indices.len() as u32,
+        })
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device) {
+        device.destroy_buffer(self.vertex_buffer, None);
+        device.free_memory(self.vertex_buffer_memory, None);
+        device.destroy_buffer(self.index_buffer, None);
+        device.free_memory(self.index_buffer_memory, None);
+    }
+}
+
+/// flattens the triangles of `mesh` into vertex positions and indices, vertices with the exact
+/// same position are shared
+fn mesh_buffers(mesh: &Mesh) -> (Vec<[f32; 3]>, Vec<u32>) {
+    let mut vertices: Vec<[f32; 3]> = vec![];
+    let mut indices = Vec::with_capacity(mesh.tris.len() * 3);
+    let mut lookup: HashMap<[u32; 3], u32> = HashMap::new();
+
+    for vert in mesh.tris.iter().flat_map(|tri| tri.verts.iter()) {
+        let position = [vert.position.x, vert.position.y, vert.position.z];
+        let index = *lookup.entry(position.map(f32::to_bits)).or_insert_with(|| {
+            vertices.push(position);
+            (vertices.len() - 1) as u32
+        });
+        indices.push(index);
+    }
+
+    (vertices, indices)
+}
+
+/// creates a host visible buffer filled with `data`
+fn create_host_buffer<T: Copy>(
+    device: &Device,
+    device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    usage: vk::BufferUsageFlags,
+    data: &[T],
+) -> Result<(vk::Buffer, vk::DeviceMemory), EngineError> {
+    let size = std::mem::size_of_val(data) as vk::DeviceSize;
+    let (buffer, buffer_memory) = create_buffer(
+        device,
+        device_memory_properties,
+        size,
+        usage,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+    )?;
+    unsafe {
+        match device.map_memory(buffer_memory, 0, size, vk::MemoryMapFlags::empty()) {
+            Ok(ptr) => {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut T, data.len());
+                device.unmap_memory(buffer_memory);
+                Ok((buffer, buffer_memory))
+            }
+            Err(e) => {
+                device.destroy_buffer(buffer, None);
+                device.free_memory(buffer_memory, None);
+                Err(e.into())
+            }
+        }
+    }
+}
+
+impl Renderer {
+    /// uploads `mesh` into GPU buffers, the returned handle can be drawn with
+    /// [`Renderer::draw_mesh`]
+    pub fn upload_mesh(&mut self, mesh: &Mesh) -> Result<MeshHandle, EngineError> {
+        let gpu_mesh = GpuMesh::new(&self.device, &self.device_memory_properties, mesh)?;
+        self.meshes.push(Some(gpu_mesh));
+        Ok(MeshHandle(self.meshes.len() - 1))
+    }
+    /// destroys the GPU buffers of `mesh`, the handle must not be drawn afterwards
+    ///
+    /// the buffers are destroyed once the frames in flight are done with them, without waiting
+    /// for the GPU
+    pub fn free_mesh(&mut self, mesh: MeshHandle) {
+        if let Some(gpu_mesh) = self.meshes.get_mut(mesh.0).and_then(Option::take) {
+            self.retired_meshes.push(RetiredMesh {
+                mesh: gpu_mesh,
+                fences: self
+                    .frames
+                    .iter()
+                    .map(|frame| frame.in_flight_fence)
+                    .collect(),
+            });
+        }
+        self.draw_calls.retain(|draw| draw.mesh != mesh);
+    }
+    /// destroys the retired meshes no frame in flight can use anymore, `fence` must have just
+    /// been waited on
+    pub(crate) fn release_retired_meshes(&mut self, fence: vk::Fence) {
+        let device = &self.device;
+        self.retired_meshes.retain_mut(|retired| {
+            retired.fences.retain(|&other| other != fence);
+            if !retired.fences.is_empty() {
+                return true;
+            }
+            unsafe { retired.mesh.destroy(device) };
+            false
+        });
+    }
+    /// destroys every retired mesh, the device must be idle
+    pub(crate) unsafe fn destroy_retired_meshes(&mut self) {
+        for mut retired in self.retired_meshes.drain(..) {
+            retired.mesh.destroy(&self.device);
+        }
+    }
+    /// queues `mesh` to be drawn in the next frame, `transform` maps the mesh into clip space
+    pub fn draw_mesh(&mut self, mesh: MeshHandle, transform: Matrix<4, 4>, color: [f32; 4]) {
+        self.draw_calls.push(DrawCall {
+            mesh,
+            transform,
+            color,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::datatypes::meshes::{Tri, Vert};
+    use crate::core::datatypes::vectors::Vector3;
+    use crate::core::layers::{Layer, UpdateContext};
+    use crate::renderer::RendererConfig;
+
+    fn tri(a: (f32, f32, f32), b: (f32, f32, f32), c: (f32, f32, f32)) -> Tri {
+        Tri {
+            verts: [a, b, c].map(|(x, y, z)| Vert {
+                position: Vector3::new(x, y, z),
+            }),
+        }
+    }
+
+    #[test]
+    fn mesh_buffers_share_vertices_test() {
+        let mesh = Mesh {
+            tris: vec![
+                tri((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+                tri((1.0, 0.0, 0.0), (1.0, 1.0, 0.0), (0.0, 1.0, 0.0)),
+            ],
+        };
+
+        let (vertices, indices) = mesh_buffers(&mesh);
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, vec![0, 1, 2, 1, 3, 2]);
+        assert_eq!(vertices[3], [1.0, 1.0, 0.0]);
+    }
+
+    /// uploads a triangle covering the whole target and draws it every frame
+    struct TriangleLayer {
+        mesh: Option<MeshHandle>,
+    }
+    impl Layer for TriangleLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn renderer_ready(&mut self, renderer: &mut Renderer) {
+            let mesh = Mesh {
+                tris: vec![tri((-1.0, -1.0, 0.5), (3.0, -1.0, 0.5), (-1.0, 3.0, 0.5))],
+            };
+            self.mesh = Some(renderer.upload_mesh(&mesh).unwrap());
+        }
+        fn update(&mut self, ctx: &mut UpdateContext) {
+            if let (Some(mesh), Some(renderer)) = (self.mesh, ctx.renderer()) {
+                renderer.draw_mesh(mesh, Matrix::identity(), [1.0, 0.0, 0.0, 1.0]);
+            }
+        }
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    #[ignore = "needs a Vulkan device"]
+    fn layer_draws_mesh_test() {
+        let mut app = crate::App::begin_build().headless().build().unwrap();
+        app.add_layer(Box::new(TriangleLayer { mesh: None }));
+        let renderer = Renderer::init_offscreen(8, 8, &RendererConfig::default()).unwrap();
+        app.set_renderer(renderer);
+
+        app.tick();
+        assert_eq!(app.renderer().unwrap().draw_calls.len(), 1);
+        app.render();
+
+        let image = app.renderer().unwrap().read_back().unwrap();
+        assert_eq!(image.get_pixel(4, 4).0, [255, 0, 0, 255]);
+    }
+}
@@ -1,4 +1,21 @@
+mod config;
+mod debug;
+mod device;
+mod frames;
 mod init;
+mod mesh;
+mod offscreen;
+mod pipeline;
+#[allow(clippy::module_inception)]
 mod renderer;
+mod swapchain;
 
+pub use config::RendererConfig;
+pub use debug::{DebugMessengerState, VULKAN_LOG_TARGET};
+pub use device::{DeviceFeature, DeviceInfo, DeviceSelection, DEVICE_ENV_VAR};
+pub use frames::{FrameData, DEFAULT_FRAMES_IN_FLIGHT};
+pub use mesh::{DrawCall, GpuMesh, MeshHandle};
+pub use offscreen::OffscreenTarget;
+pub use pipeline::{MeshPipeline, MeshPushConstants};
 pub use renderer::*;
+pub use swapchain::{PresentMode, SwapchainTarget};
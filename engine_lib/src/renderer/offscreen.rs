@@ -0,0 +1,318 @@
+use ash::{vk, Device};
+
+use super::init::{create_buffer, find_memorytype_index, record_submit_commandbuffer};
+use super::{RenderTarget, Renderer, RendererConfig};
+use crate::core::error::EngineError;
+
+/// Renders into an image owned by the renderer, no window or surface is needed
+///
+/// between frames the image is kept in `TRANSFER_SRC_OPTIMAL` so it can be read back at any time
+pub struct OffscreenTarget {
+    pub image: vk::Image,
+    pub image_view: vk::ImageView,
+    pub image_memory: vk::DeviceMemory,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+}
+
+impl OffscreenTarget {
+    pub const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+    pub(crate) fn new(
+        device: &Device,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        extent: vk::Extent2D,
+    ) -> Result<Self, EngineError> {
+        unsafe {
+            let image_create_info = vk::ImageCreateInfo::default()
+                .image_type(vk::ImageType::TYPE_2D)
+                .format(Self::FORMAT)
+                .extent(extent.into())
+                .mip_levels(1)
+                .array_layers(1)
+                .samples(vk::SampleCountFlags::TYPE_1)
+                .tiling(vk::ImageTiling::OPTIMAL)
+                .usage(
+                    vk::ImageUsageFlags::COLOR_ATTACHMENT
+                        | vk::ImageUsageFlags::TRANSFER_SRC
+                        | vk::ImageUsageFlags::TRANSFER_DST,
+                )
+                .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+            let image = device.create_image(&image_create_info, None)?;
+            let image_memory = (|| {
+                let image_memory_req = device.get_image_memory_requirements(image);
+                let image_memory_index = find_memorytype_index(
+                    &image_memory_req,
+                    device_memory_properties,
+                    vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                )
+                .ok_or(EngineError::NoSuitableMemoryType)?;
+
+                let image_allocate_info = vk::MemoryAllocateInfo::default()
+                    .allocation_size(image_memory_req.size)
+                    .memory_type_index(image_memory_index);
+
+                let image_memory = device.allocate_memory(&image_allocate_info, None)?;
+                if let Err(e) = device.bind_image_memory(image, image_memory, 0) {
+                    device.free_memory(image_memory, None);
+                    return Err(e.into());
+                }
+                Ok(image_memory)
+            })();
+            let image_memory = match image_memory {
+                Ok(image_memory) => image_memory,
+                Err(e) => {
+                    device.destroy_image(image, None);
+                    return Err(e);
+                }
+            };
+
+            let image_view_info = vk::ImageViewCreateInfo::default()
+                .subresource_range(
+                    vk::ImageSubresourceRange::default()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .level_count(1)
+                        .layer_count(1),
+                )
+                .image(image)
+                .format(Self::FORMAT)
+                .view_type(vk::ImageViewType::TYPE_2D);
+
+            let image_view = match device.create_image_view(&image_view_info, None) {
+                Ok(image_view) => image_view,
+                Err(e) => {
+                    device.destroy_image(image, None);
+                    device.free_memory(image_memory, None);
+                    return Err(e.into());
+                }
+            };
+
+            Ok(OffscreenTarget {
+                image,
+                image_view,
+                image_memory,
+                format: Self::FORMAT,
+                extent,
+            })
+        }
+    }
+
+    /// records a clear of the whole image, leaving it in `TRANSFER_SRC_OPTIMAL`
+    pub(crate) fn record_clear(
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        old_layout: vk::ImageLayout,
+        clear_color: [f32; 4],
+    ) {
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .level_count(1)
+            .layer_count(1);
+        let to_transfer_dst = vk::ImageMemoryBarrier::default()
+            .image(image)
+            .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .old_layout(old_layout)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .subresource_range(subresource_range);
+        let to_transfer_src = vk::ImageMemoryBarrier::default()
+            .image(image)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .subresource_range(subresource_range);
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_transfer_dst],
+            );
+            device.cmd_clear_color_image(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    float32: clear_color,
+                },
+                &[subresource_range],
+            );
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_transfer_src],
+            );
+        }
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device) {
+        device.destroy_image_view(self.image_view, None);
+        device.destroy_image(self.image, None);
+        device.free_memory(self.image_memory, None);
+    }
+}
+
+impl Renderer {
+    /// creates a renderer that draws into a `width` x `height` image instead of a window,
+    /// the result can be fetched with [`Renderer::read_back`]
+    pub fn init_offscreen(
+        width: u32,
+        height: u32,
+        config: &RendererConfig,
+    ) -> Result<Self, EngineError> {
+        let (entry, instance, debug_utils_loader, debug_call_back, debug_messenger_state) =
+            Self::create_instance(config, &[])?;
+
+        let (pdevice, queue_family_index, device_candidates) =
+            Self::pick_physical_device(&instance, config, &[], |_, _| true)?;
+        let (device, present_queue) =
+            Self::create_device(&instance, config, pdevice, queue_family_index, &[])?;
+
+        let device_memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(pdevice) };
+        let target = OffscreenTarget::new(
+            &device,
+            &device_memory_properties,
+            vk::Extent2D { width, height },
+        )?;
+
+        let renderer = Self::from_parts(
+            entry,
+            instance,
+            debug_utils_loader,
+            debug_call_back,
+            debug_messenger_state,
+            pdevice,
+            device_candidates,
+            queue_family_index,
+            device,
+            present_queue,
+            RenderTarget::Offscreen(target),
+            config,
+        )?;
+
+        if let RenderTarget::Offscreen(target) = &renderer.target {
+            let image = target.image;
+            let clear_color = renderer.clear_color;
+            record_submit_commandbuffer(
+                &renderer.device,
+                renderer.setup_command_buffer,
+                renderer.setup_commands_reuse_fence,
+                renderer.present_queue,
+                &[],
+                &[],
+                &[],
+                |device, setup_command_buffer| {
+                    OffscreenTarget::record_clear(
+                        device,
+                        setup_command_buffer,
+                        image,
+                        vk::ImageLayout::UNDEFINED,
+                        clear_color,
+                    );
+                },
+            )?;
+        }
+
+        Ok(renderer)
+    }
+
+    /// copies the offscreen color attachment to host memory
+    ///
+    /// the returned image can be written to disk with [`image::RgbaImage::save`]
+    pub fn read_back(&self) -> Result<image::RgbaImage, EngineError> {
+        let RenderTarget::Offscreen(target) = &self.target else {
+            return Err(EngineError::ReadBack(
+                "read_back requires an offscreen render target",
+            ));
+        };
+        let extent = target.extent;
+        let image = target.image;
+        let size = extent.width as vk::DeviceSize * extent.height as vk::DeviceSize * 4;
+
+        let (buffer, buffer_memory) = create_buffer(
+            &self.device,
+            &self.device_memory_properties,
+            size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        // the buffer is freed below whether or not the copy worked
+        let pixels = (|| unsafe {
+            // the image may still be written to by a frame that is in flight
+            self.device.device_wait_idle()?;
+
+            record_submit_commandbuffer(
+                &self.device,
+                self.setup_command_buffer,
+                self.setup_commands_reuse_fence,
+                self.present_queue,
+                &[],
+                &[],
+                &[],
+                |device, setup_command_buffer| {
+                    let region = vk::BufferImageCopy::default()
+                        .image_subresource(
+                            vk::ImageSubresourceLayers::default()
+                                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                                .layer_count(1),
+                        )
+                        .image_extent(extent.into());
+                    device.cmd_copy_image_to_buffer(
+                        setup_command_buffer,
+                        image,
+                        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                        buffer,
+                        &[region],
+                    );
+                    let host_barrier = vk::BufferMemoryBarrier::default()
+                        .buffer(buffer)
+                        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags::HOST_READ)
+                        .size(vk::WHOLE_SIZE);
+                    device.cmd_pipeline_barrier(
+                        setup_command_buffer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::HOST,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[host_barrier],
+                        &[],
+                    );
+                },
+            )?;
+            self.device
+                .wait_for_fences(&[self.setup_commands_reuse_fence], true, u64::MAX)?;
+
+            let ptr =
+                self.device
+                    .map_memory(buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+            let pixels = std::slice::from_raw_parts(ptr as *const u8, size as usize).to_vec();
+            self.device.unmap_memory(buffer_memory);
+            Ok::<_, EngineError>(pixels)
+        })();
+        unsafe {
+            if pixels.is_err() {
+                // a failed wait may leave the copy running
+                _ = self.device.device_wait_idle();
+            }
+            self.device.destroy_buffer(buffer, None);
+            self.device.free_memory(buffer_memory, None);
+        }
+
+        image::RgbaImage::from_raw(extent.width, extent.height, pixels?).ok_or(
+            EngineError::ReadBack("read back buffer does not match the image size"),
+        )
+    }
+}
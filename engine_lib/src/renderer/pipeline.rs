@@ -0,0 +1,255 @@
+use std::io::Cursor;
+
+use ash::{util::read_spv, vk, Device};
+
+use super::init::DEPTH_FORMAT;
+use crate::core::error::EngineError;
+
+// compiled from the glsl sources next to them, e.g.
+// `naga --input-kind glsl --shader-stage vert --keep-coordinate-space mesh.vert mesh.vert.spv`
+const MESH_VERTEX_SHADER: &[u8] = include_bytes!("shaders/mesh.vert.spv");
+const MESH_FRAGMENT_SHADER: &[u8] = include_bytes!("shaders/mesh.frag.spv");
+
+/// Per draw data pushed to the mesh shaders, must match the `PushConstants` block in
+/// `shaders/mesh.vert` and `shaders/mesh.frag`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct MeshPushConstants {
+    pub transform: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
+
+impl MeshPushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                (self as *const Self) as *const u8,
+                std::mem::size_of::<Self>(),
+            )
+        }
+    }
+}
+
+/// creates a render pass with a single subpass drawing into one color attachment and the depth
+/// attachment, the color attachment ends up in `final_layout`
+pub fn create_render_pass(
+    device: &Device,
+    color_format: vk::Format,
+    final_layout: vk::ImageLayout,
+) -> Result<vk::RenderPass, vk::Result> {
+    let renderpass_attachments = [
+        vk::AttachmentDescription {
+            format: color_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout,
+            ..Default::default()
+        },
+        vk::AttachmentDescription {
+            format: DEPTH_FORMAT,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            ..Default::default()
+        },
+    ];
+    let color_attachment_refs = [vk::AttachmentReference {
+        attachment: 0,
+        layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    }];
+    let depth_attachment_ref = vk::AttachmentReference {
+        attachment: 1,
+        layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    };
+    let dependencies = [
+        vk::SubpassDependency {
+            src_subpass: vk::SUBPASS_EXTERNAL,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
+                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ..Default::default()
+        },
+        // makes the color attachment visible to transfers, used when reading back offscreen
+        // targets
+        vk::SubpassDependency {
+            src_subpass: 0,
+            dst_subpass: vk::SUBPASS_EXTERNAL,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            dst_stage_mask: vk::PipelineStageFlags::TRANSFER,
+            src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+            ..Default::default()
+        },
+    ];
+
+    let subpass = vk::SubpassDescription::default()
+        .color_attachments(&color_attachment_refs)
+        .depth_stencil_attachment(&depth_attachment_ref)
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS);
+
+    let renderpass_create_info = vk::RenderPassCreateInfo::default()
+        .attachments(&renderpass_attachments)
+        .subpasses(std::slice::from_ref(&subpass))
+        .dependencies(&dependencies);
+
+    unsafe { device.create_render_pass(&renderpass_create_info, None) }
+}
+
+/// The pipeline used to draw [`crate::core::datatypes::meshes::Mesh`]es, vertices only carry a
+/// position and the transform and color are pushed per draw
+pub struct MeshPipeline {
+    pub pipeline: vk::Pipeline,
+    pub pipeline_layout: vk::PipelineLayout,
+    pub vertex_shader_module: vk::ShaderModule,
+    pub fragment_shader_module: vk::ShaderModule,
+}
+
+impl MeshPipeline {
+    pub fn new(device: &Device, render_pass: vk::RenderPass) -> Result<Self, EngineError> {
+        unsafe {
+            let vertex_code =
+                read_spv(&mut Cursor::new(MESH_VERTEX_SHADER)).map_err(EngineError::Shader)?;
+            let vertex_shader_info = vk::ShaderModuleCreateInfo::default().code(&vertex_code);
+            let fragment_code =
+                read_spv(&mut Cursor::new(MESH_FRAGMENT_SHADER)).map_err(EngineError::Shader)?;
+            let fragment_shader_info = vk::ShaderModuleCreateInfo::default().code(&fragment_code);
+
+            let vertex_shader_module = device.create_shader_module(&vertex_shader_info, None)?;
+            let fragment_shader_module =
+                device.create_shader_module(&fragment_shader_info, None)?;
+
+            let push_constant_ranges = [vk::PushConstantRange {
+                stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                offset: 0,
+                size: std::mem::size_of::<MeshPushConstants>() as u32,
+            }];
+            let layout_create_info =
+                vk::PipelineLayoutCreateInfo::default().push_constant_ranges(&push_constant_ranges);
+            let pipeline_layout = device.create_pipeline_layout(&layout_create_info, None)?;
+
+            let shader_entry_name = c"main";
+            let shader_stage_create_infos = [
+                vk::PipelineShaderStageCreateInfo {
+                    module: vertex_shader_module,
+                    p_name: shader_entry_name.as_ptr(),
+                    stage: vk::ShaderStageFlags::VERTEX,
+                    ..Default::default()
+                },
+                vk::PipelineShaderStageCreateInfo {
+                    module: fragment_shader_module,
+                    p_name: shader_entry_name.as_ptr(),
+                    stage: vk::ShaderStageFlags::FRAGMENT,
+                    ..Default::default()
+                },
+            ];
+            let vertex_input_binding_descriptions = [vk::VertexInputBindingDescription {
+                binding: 0,
+                stride: std::mem::size_of::<[f32; 3]>() as u32,
+                input_rate: vk::VertexInputRate::VERTEX,
+            }];
+            let vertex_input_attribute_descriptions = [vk::VertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: 0,
+            }];
+
+            let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
+                .vertex_attribute_descriptions(&vertex_input_attribute_descriptions)
+                .vertex_binding_descriptions(&vertex_input_binding_descriptions);
+            let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+                topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+                ..Default::default()
+            };
+            // the viewport and scissor are set while recording so the pipeline survives resizes
+            let viewport_state_info = vk::PipelineViewportStateCreateInfo::default()
+                .viewport_count(1)
+                .scissor_count(1);
+
+            let rasterization_info = vk::PipelineRasterizationStateCreateInfo {
+                front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+                line_width: 1.0,
+                polygon_mode: vk::PolygonMode::FILL,
+                cull_mode: vk::CullModeFlags::NONE,
+                ..Default::default()
+            };
+            let multisample_state_info = vk::PipelineMultisampleStateCreateInfo {
+                rasterization_samples: vk::SampleCountFlags::TYPE_1,
+                ..Default::default()
+            };
+            let noop_stencil_state = vk::StencilOpState {
+                fail_op: vk::StencilOp::KEEP,
+                pass_op: vk::StencilOp::KEEP,
+                depth_fail_op: vk::StencilOp::KEEP,
+                compare_op: vk::CompareOp::ALWAYS,
+                ..Default::default()
+            };
+            let depth_state_info = vk::PipelineDepthStencilStateCreateInfo {
+                depth_test_enable: 1,
+                depth_write_enable: 1,
+                depth_compare_op: vk::CompareOp::LESS_OR_EQUAL,
+                front: noop_stencil_state,
+                back: noop_stencil_state,
+                max_depth_bounds: 1.0,
+                ..Default::default()
+            };
+            let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+                blend_enable: 0,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+                ..Default::default()
+            }];
+            let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default()
+                .logic_op(vk::LogicOp::CLEAR)
+                .attachments(&color_blend_attachment_states);
+
+            let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+            let dynamic_state_info =
+                vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_state);
+
+            let graphic_pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+                .stages(&shader_stage_create_infos)
+                .vertex_input_state(&vertex_input_state_info)
+                .input_assembly_state(&vertex_input_assembly_state_info)
+                .viewport_state(&viewport_state_info)
+                .rasterization_state(&rasterization_info)
+                .multisample_state(&multisample_state_info)
+                .depth_stencil_state(&depth_state_info)
+                .color_blend_state(&color_blend_state)
+                .dynamic_state(&dynamic_state_info)
+                .layout(pipeline_layout)
+                .render_pass(render_pass);
+
+            let pipeline = device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &[graphic_pipeline_info],
+                    None,
+                )
+                .map_err(|(_, e)| e)?[0];
+
+            Ok(MeshPipeline {
+                pipeline,
+                pipeline_layout,
+                vertex_shader_module,
+                fragment_shader_module,
+            })
+        }
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device) {
+        device.destroy_pipeline(self.pipeline, None);
+        device.destroy_pipeline_layout(self.pipeline_layout, None);
+        device.destroy_shader_module(self.vertex_shader_module, None);
+        device.destroy_shader_module(self.fragment_shader_module, None);
+    }
+}
@@ -1,58 +1,263 @@
-use std::sync::Arc;
+use ash::{ext::debug_utils, vk, Device, Entry, Instance};
 
-use ash::{
-    ext::debug_utils,
-    khr::{surface, swapchain},
-    vk, Device, Entry, Instance,
-};
-use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use super::debug::DebugMessengerState;
+use super::device::DeviceInfo;
+use super::frames::FrameData;
+use super::init::record_submit_commandbuffer;
+use super::mesh::{DrawCall, GpuMesh, RetiredMesh};
+use super::offscreen::OffscreenTarget;
+use super::pipeline::{MeshPipeline, MeshPushConstants};
+use super::swapchain::SwapchainTarget;
 
 pub struct Renderer {
     pub entry: Entry,
     pub instance: Instance,
     pub device: Device,
-    pub surface_loader: surface::Instance,
-    pub swapchain_loader: swapchain::Device,
     pub debug_utils_loader: debug_utils::Instance,
-    pub window: Arc<winit::window::Window>,
     pub debug_call_back: vk::DebugUtilsMessengerEXT,
+    /// the user data of `debug_call_back`, boxed so its address doesn't change
+    pub debug_messenger_state: Box<DebugMessengerState>,
 
     pub pdevice: vk::PhysicalDevice,
+    /// every physical device considered when picking `pdevice`
+    pub device_candidates: Vec<DeviceInfo>,
+    /// the index of `pdevice` in `device_candidates`
+    pub device_index: usize,
     pub device_memory_properties: vk::PhysicalDeviceMemoryProperties,
     pub queue_family_index: u32,
     pub present_queue: vk::Queue,
 
-    pub surface: vk::SurfaceKHR,
-    pub surface_format: vk::SurfaceFormatKHR,
-    pub surface_resolution: vk::Extent2D,
-
-    pub swapchain: vk::SwapchainKHR,
-    pub present_images: Vec<vk::Image>,
-    pub present_image_views: Vec<vk::ImageView>,
+    pub target: RenderTarget,
 
     pub pool: vk::CommandPool,
-    pub draw_command_buffer: vk::CommandBuffer,
     pub setup_command_buffer: vk::CommandBuffer,
+    pub frames: Vec<FrameData>,
 
     pub depth_image: vk::Image,
     pub depth_image_view: vk::ImageView,
     pub depth_image_memory: vk::DeviceMemory,
 
-    pub present_complete_semaphore: vk::Semaphore,
-    pub rendering_complete_semaphore: vk::Semaphore,
+    pub render_pass: vk::RenderPass,
+    pub framebuffers: Vec<vk::Framebuffer>,
+    pub mesh_pipeline: MeshPipeline,
 
-    pub draw_commands_reuse_fence: vk::Fence,
     pub setup_commands_reuse_fence: vk::Fence,
-    //pub in_flight_fences: Vec<vk::Fence>,
+    pub clear_color: [f32; 4],
+    /// set when the swapchain no longer matches the surface, it is rebuilt before the next frame
+    pub swapchain_out_of_date: bool,
+
+    pub(crate) meshes: Vec<Option<GpuMesh>>,
+    pub(crate) draw_calls: Vec<DrawCall>,
+    pub(crate) retired_meshes: Vec<RetiredMesh>,
+}
+
+/// What the renderer draws into, either a window's swapchain or an image owned by the renderer
+pub enum RenderTarget {
+    Swapchain(SwapchainTarget),
+    Offscreen(OffscreenTarget),
+}
+
+impl RenderTarget {
+    pub fn extent(&self) -> vk::Extent2D {
+        match self {
+            RenderTarget::Swapchain(target) => target.surface_resolution,
+            RenderTarget::Offscreen(target) => target.extent,
+        }
+    }
+    pub fn color_format(&self) -> vk::Format {
+        match self {
+            RenderTarget::Swapchain(target) => target.surface_format.format,
+            RenderTarget::Offscreen(target) => target.format,
+        }
+    }
+    /// the views the render pass draws into, framebuffers are created for each of them
+    pub fn color_image_views(&self) -> &[vk::ImageView] {
+        match self {
+            RenderTarget::Swapchain(target) => &target.present_image_views,
+            RenderTarget::Offscreen(target) => std::slice::from_ref(&target.image_view),
+        }
+    }
+    /// the layout the color attachment is left in at the end of a frame
+    pub fn final_layout(&self) -> vk::ImageLayout {
+        match self {
+            RenderTarget::Swapchain(_) => vk::ImageLayout::PRESENT_SRC_KHR,
+            RenderTarget::Offscreen(_) => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        }
+    }
 }
 
 impl Renderer {
-    pub fn render(&mut self, frame: usize) {
-        self.draw_frame(frame);
+    /// draws and presents a frame with every draw call queued since the last frame
+    ///
+    /// `frame` is the app's frame counter, it selects which of the frames in flight is used
+    pub fn render(&mut self, frame: usize) -> Result<(), vk::Result> {
+        self.draw_frame(frame)
+    }
+    fn draw_frame(&mut self, frame: usize) -> Result<(), vk::Result> {
+        if let RenderTarget::Swapchain(target) = &self.target {
+            if target.is_minimized() {
+                self.draw_calls.clear();
+                return Ok(());
+            }
+        }
+        if self.swapchain_out_of_date {
+            self.recreate_swapchain()?;
+        }
+
+        let frame = &self.frames[frame % self.frames.len()];
+        let command_buffer = frame.command_buffer;
+        let in_flight_fence = frame.in_flight_fence;
+        let present_complete_semaphore = frame.present_complete_semaphore;
+
+        unsafe {
+            // waits until the GPU is done with the last frame that used these resources
+            self.device
+                .wait_for_fences(&[in_flight_fence], true, u64::MAX)?;
+            self.release_retired_meshes(in_flight_fence);
+
+            let image_index = match &self.target {
+                RenderTarget::Swapchain(target) => {
+                    match target.swapchain_loader.acquire_next_image(
+                        target.swapchain,
+                        u64::MAX,
+                        present_complete_semaphore,
+                        vk::Fence::null(),
+                    ) {
+                        Ok((image_index, suboptimal)) => {
+                            self.swapchain_out_of_date |= suboptimal;
+                            image_index
+                        }
+                        Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                            self.swapchain_out_of_date = true;
+                            self.draw_calls.clear();
+                            return Ok(());
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                RenderTarget::Offscreen(_) => 0,
+            };
+            let framebuffer = self.framebuffers[image_index as usize];
+
+            let wait_semaphores = [present_complete_semaphore];
+            let (wait_mask, wait_semaphores, signal_semaphores): (&[_], &[_], &[_]) =
+                match &self.target {
+                    RenderTarget::Swapchain(target) => (
+                        &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
+                        &wait_semaphores,
+                        std::slice::from_ref(
+                            &target.rendering_complete_semaphores[image_index as usize],
+                        ),
+                    ),
+                    RenderTarget::Offscreen(_) => (&[], &[], &[]),
+                };
+
+            record_submit_commandbuffer(
+                &self.device,
+                command_buffer,
+                in_flight_fence,
+                self.present_queue,
+                wait_mask,
+                wait_semaphores,
+                signal_semaphores,
+                |_, draw_command_buffer| self.record_frame(draw_command_buffer, framebuffer),
+            )?;
+            self.draw_calls.clear();
+
+            if let RenderTarget::Swapchain(target) = &self.target {
+                let swapchains = [target.swapchain];
+                let image_indices = [image_index];
+                let present_info = vk::PresentInfoKHR::default()
+                    .wait_semaphores(signal_semaphores)
+                    .swapchains(&swapchains)
+                    .image_indices(&image_indices);
+
+                match target
+                    .swapchain_loader
+                    .queue_present(self.present_queue, &present_info)
+                {
+                    Ok(suboptimal) => self.swapchain_out_of_date |= suboptimal,
+                    Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.swapchain_out_of_date = true,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
     }
-    fn draw_frame(&mut self, frame: usize) {
-        let frame = frame % self.present_image_views.len();
-        println!("{} max frames in flight", self.present_image_views.len());
-        //self.device.wait_for_fences(self.in_flight_fences[frame])
+    /// records the render pass drawing every queued draw call into `framebuffer`
+    fn record_frame(&self, command_buffer: vk::CommandBuffer, framebuffer: vk::Framebuffer) {
+        let extent = self.target.extent();
+        let clear_values = [
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: self.clear_color,
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
+        let render_pass_begin_info = vk::RenderPassBeginInfo::default()
+            .render_pass(self.render_pass)
+            .framebuffer(framebuffer)
+            .render_area(extent.into())
+            .clear_values(&clear_values);
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: extent.width as f32,
+            height: extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+        let scissors = [extent.into()];
+
+        unsafe {
+            self.device.cmd_begin_render_pass(
+                command_buffer,
+                &render_pass_begin_info,
+                vk::SubpassContents::INLINE,
+            );
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.mesh_pipeline.pipeline,
+            );
+            self.device.cmd_set_viewport(command_buffer, 0, &viewports);
+            self.device.cmd_set_scissor(command_buffer, 0, &scissors);
+
+            for draw_call in self.draw_calls.iter() {
+                let Some(Some(mesh)) = self.meshes.get(draw_call.mesh.0) else {
+                    continue;
+                };
+                let push_constants = MeshPushConstants {
+                    transform: draw_call.transform.values,
+                    color: draw_call.color,
+                };
+
+                self.device
+                    .cmd_bind_vertex_buffers(command_buffer, 0, &[mesh.vertex_buffer], &[0]);
+                self.device.cmd_bind_index_buffer(
+                    command_buffer,
+                    mesh.index_buffer,
+                    0,
+                    vk::IndexType::UINT32,
+                );
+                self.device.cmd_push_constants(
+                    command_buffer,
+                    self.mesh_pipeline.pipeline_layout,
+                    vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                    0,
+                    push_constants.as_bytes(),
+                );
+                self.device
+                    .cmd_draw_indexed(command_buffer, mesh.index_count, 1, 0, 0, 0);
+            }
+
+            self.device.cmd_end_render_pass(command_buffer);
+        }
     }
 }
@@ -0,0 +1,12 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 transform;
+    vec4 color;
+} pc;
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    out_color = pc.color;
+}
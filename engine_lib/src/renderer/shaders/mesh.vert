@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+
+layout(push_constant) uniform PushConstants {
+    mat4 transform;
+    vec4 color;
+} pc;
+
+void main() {
+    // engine matrices are row-major, so multiplying from the left applies them to column vectors
+    gl_Position = vec4(position, 1.0) * pc.transform;
+}
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use ash::{
+    khr::{surface, swapchain},
+    vk, Device, Instance,
+};
+
+use super::init::{create_depth_image, create_framebuffers};
+use super::{RenderTarget, Renderer};
+use crate::core::error::EngineError;
+
+/// How frames are queued for presentation, falls back to [`PresentMode::Fifo`] when the
+/// preferred mode isn't supported
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PresentMode {
+    /// waits for vertical blank, no tearing and the frame rate is capped to the display
+    Fifo,
+    /// waits for vertical blank but replaces the queued frame, no tearing and low latency
+    #[default]
+    Mailbox,
+    /// presents immediately, may tear
+    Immediate,
+}
+
+impl PresentMode {
+    fn vk_mode(self) -> vk::PresentModeKHR {
+        match self {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
+
+/// Renders to a window through a surface and swapchain
+pub struct SwapchainTarget {
+    pub window: Arc<winit::window::Window>,
+    pub surface_loader: surface::Instance,
+    pub swapchain_loader: swapchain::Device,
+
+    pub surface: vk::SurfaceKHR,
+    pub surface_format: vk::SurfaceFormatKHR,
+    pub surface_resolution: vk::Extent2D,
+    pub present_mode: PresentMode,
+
+    pub swapchain: vk::SwapchainKHR,
+    pub present_images: Vec<vk::Image>,
+    pub present_image_views: Vec<vk::ImageView>,
+    /// signaled when a frame was rendered into the present image with the same index, and
+    /// waited on by its presentation
+    ///
+    /// kept per image rather than per frame in flight, a frame's fence doesn't show that the
+    /// presentation waiting on its semaphore is done, but an image is only acquired again after
+    /// it was presented
+    pub rendering_complete_semaphores: Vec<vk::Semaphore>,
+}
+
+impl SwapchainTarget {
+    pub(crate) fn new(
+        instance: &Instance,
+        device: &Device,
+        pdevice: vk::PhysicalDevice,
+        window: Arc<winit::window::Window>,
+        surface_loader: surface::Instance,
+        surface: vk::SurfaceKHR,
+        present_mode: PresentMode,
+    ) -> Result<Self, EngineError> {
+        let surface_format =
+            unsafe { surface_loader.get_physical_device_surface_formats(pdevice, surface)?[0] };
+        let swapchain_loader = swapchain::Device::new(instance, device);
+
+        let mut target = SwapchainTarget {
+            window,
+            surface_loader,
+            swapchain_loader,
+            surface,
+            surface_format,
+            surface_resolution: vk::Extent2D::default(),
+            present_mode,
+            swapchain: vk::SwapchainKHR::null(),
+            present_images: vec![],
+            present_image_views: vec![],
+            rendering_complete_semaphores: vec![],
+        };
+        target.create_swapchain(device, pdevice)?;
+
+        Ok(target)
+    }
+
+    /// (re)creates the swapchain and its image views for the current size of the surface, the
+    /// previous swapchain is retired and destroyed
+    ///
+    /// the device must be idle when recreating
+    pub(crate) fn create_swapchain(
+        &mut self,
+        device: &Device,
+        pdevice: vk::PhysicalDevice,
+    ) -> Result<(), vk::Result> {
+        unsafe {
+            let surface_capabilities = self
+                .surface_loader
+                .get_physical_device_surface_capabilities(pdevice, self.surface)?;
+            let mut desired_image_count = surface_capabilities.min_image_count + 1;
+            if surface_capabilities.max_image_count > 0
+                && desired_image_count > surface_capabilities.max_image_count
+            {
+                desired_image_count = surface_capabilities.max_image_count;
+            }
+            let surface_resolution = match surface_capabilities.current_extent.width {
+                u32::MAX => vk::Extent2D {
+                    width: self.window.inner_size().width,
+                    height: self.window.inner_size().height,
+                },
+                _ => surface_capabilities.current_extent,
+            };
+            let pre_transform = if surface_capabilities
+                .supported_transforms
+                .contains(vk::SurfaceTransformFlagsKHR::IDENTITY)
+            {
+                vk::SurfaceTransformFlagsKHR::IDENTITY
+            } else {
+                surface_capabilities.current_transform
+            };
+            let present_modes = self
+                .surface_loader
+                .get_physical_device_surface_present_modes(pdevice, self.surface)?;
+            let present_mode = present_modes
+                .iter()
+                .cloned()
+                .find(|&mode| mode == self.present_mode.vk_mode())
+                .unwrap_or(vk::PresentModeKHR::FIFO);
+
+            let old_swapchain = self.swapchain;
+            let swapchain_create_info = vk::SwapchainCreateInfoKHR::default()
+                .surface(self.surface)
+                .min_image_count(desired_image_count)
+                .image_color_space(self.surface_format.color_space)
+                .image_format(self.surface_format.format)
+                .image_extent(surface_resolution)
+                .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+                .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .pre_transform(pre_transform)
+                .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+                .present_mode(present_mode)
+                .clipped(true)
+                .image_array_layers(1)
+                .old_swapchain(old_swapchain);
+
+            let swapchain = self
+                .swapchain_loader
+                .create_swapchain(&swapchain_create_info, None)?;
+            self.destroy_swapchain(device);
+            self.swapchain = swapchain;
+            self.surface_resolution = surface_resolution;
+
+            self.present_images = self.swapchain_loader.get_swapchain_images(swapchain)?;
+            for &image in self.present_images.iter() {
+                let create_view_info = vk::ImageViewCreateInfo::default()
+                    .view_type(vk::ImageViewType::TYPE_2D)
+                    .format(self.surface_format.format)
+                    .components(vk::ComponentMapping {
+                        r: vk::ComponentSwizzle::R,
+                        g: vk::ComponentSwizzle::G,
+                        b: vk::ComponentSwizzle::B,
+                        a: vk::ComponentSwizzle::A,
+                    })
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+                    .image(image);
+                self.present_image_views
+                    .push(device.create_image_view(&create_view_info, None)?);
+            }
+
+            if self.rendering_complete_semaphores.len() != self.present_images.len() {
+                self.destroy_semaphores(device);
+                for _ in 0..self.present_images.len() {
+                    self.rendering_complete_semaphores
+                        .push(device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None)?);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// whether the window is minimized, nothing can be presented until it is restored
+    pub fn is_minimized(&self) -> bool {
+        let size = self.window.inner_size();
+        size.width == 0 || size.height == 0
+    }
+
+    /// destroys the swapchain and its image views, must be called before the device is destroyed
+    pub(crate) unsafe fn destroy_swapchain(&mut self, device: &Device) {
+        for image_view in self.present_image_views.drain(..) {
+            device.destroy_image_view(image_view, None);
+        }
+        self.swapchain_loader
+            .destroy_swapchain(self.swapchain, None);
+        self.swapchain = vk::SwapchainKHR::null();
+    }
+    /// destroys the semaphores of the present images, the device must be idle
+    pub(crate) unsafe fn destroy_semaphores(&mut self, device: &Device) {
+        for semaphore in self.rendering_complete_semaphores.drain(..) {
+            device.destroy_semaphore(semaphore, None);
+        }
+    }
+    /// destroys the surface, must be called after the device is destroyed
+    pub(crate) unsafe fn destroy_surface(&mut self) {
+        self.surface_loader.destroy_surface(self.surface, None);
+    }
+}
+
+impl Renderer {
+    /// notifies the renderer that the window changed size, the swapchain is rebuilt before the
+    /// next frame
+    pub fn window_resized(&mut self) {
+        self.swapchain_out_of_date = true;
+    }
+    /// changes the preferred present mode, the swapchain is rebuilt before the next frame
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        if let RenderTarget::Swapchain(target) = &mut self.target {
+            if target.present_mode != present_mode {
+                target.present_mode = present_mode;
+                self.swapchain_out_of_date = true;
+            }
+        }
+    }
+    /// rebuilds the swapchain, its image views, the depth buffer and the framebuffers for the
+    /// current size of the window, the render complete semaphores are rebuilt when the number of
+    /// images changed
+    pub fn recreate_swapchain(&mut self) -> Result<(), vk::Result> {
+        let RenderTarget::Swapchain(target) = &mut self.target else {
+            return Ok(());
+        };
+        if target.is_minimized() {
+            return Ok(());
+        }
+
+        unsafe {
+            self.device.device_wait_idle()?;
+
+            for framebuffer in self.framebuffers.drain(..) {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+            self.device.destroy_image_view(self.depth_image_view, None);
+            self.device.destroy_image(self.depth_image, None);
+            self.device.free_memory(self.depth_image_memory, None);
+            self.depth_image_view = vk::ImageView::null();
+            self.depth_image = vk::Image::null();
+            self.depth_image_memory = vk::DeviceMemory::null();
+
+            target.create_swapchain(&self.device, self.pdevice)?;
+
+            let (depth_image, depth_image_memory, depth_image_view) = create_depth_image(
+                &self.device,
+                &self.device_memory_properties,
+                target.surface_resolution,
+            )?;
+            self.depth_image = depth_image;
+            self.depth_image_memory = depth_image_memory;
+            self.depth_image_view = depth_image_view;
+
+            self.framebuffers = create_framebuffers(
+                &self.device,
+                self.render_pass,
+                &self.target,
+                self.depth_image_view,
+            )?;
+        }
+        self.swapchain_out_of_date = false;
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use matrices::Matrix;
 
 pub use crate::core::datatypes::*;
 
+#[allow(dead_code)]
 pub struct Transform {
     position: vectors::Vector3,
     rotation: vectors::Vector3,
@@ -15,10 +16,9 @@ impl Transform {
             [0.0, self.scale.y, 0.0],
             [0.0, 0.0, self.scale.z],
         ]);
-        let matrix = matrix
+        matrix
             * matrices::RotationMatrix3D::get_z(self.rotation.z)
             * matrices::RotationMatrix3D::get_y(self.rotation.y)
-            * matrices::RotationMatrix3D::get_x(self.rotation.x);
-        matrix
+            * matrices::RotationMatrix3D::get_x(self.rotation.x)
     }
 }
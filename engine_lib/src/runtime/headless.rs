@@ -0,0 +1,161 @@
+pub type StopCondition = dyn FnMut(&crate::App) -> bool;
+
+/// Configuration for running the app without a window or renderer, the frame loop is driven by a
+/// plain loop instead of the winit event loop
+pub struct HeadlessConfig {
+    tick_limit: Option<u64>,
+    stop_condition: Option<Box<StopCondition>>,
+}
+
+impl HeadlessConfig {
+    pub fn new() -> Self {
+        HeadlessConfig {
+            tick_limit: None,
+            stop_condition: None,
+        }
+    }
+}
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadlessConfig {
+    /// stops the loop once the app has run `ticks` frames
+    pub fn with_tick_limit(mut self, ticks: u64) -> Self {
+        self.tick_limit = Some(ticks);
+        self
+    }
+    /// stops the loop once `f` returns true, checked before every tick
+    pub fn with_stop_condition<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&crate::App) -> bool + 'static,
+    {
+        self.stop_condition = Some(Box::new(f));
+        self
+    }
+
+    pub(crate) fn should_stop(&mut self, app: &crate::App) -> bool {
+        if let Some(limit) = self.tick_limit {
+            if app.frame() >= limit {
+                return true;
+            }
+        }
+        match self.stop_condition.as_mut() {
+            Some(f) => (f)(app),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::core::events::{event, EventEvaluateState, EventInfo, EventListener};
+    use crate::core::layers::Layer;
+
+    struct CountingLayer {
+        updates: Rc<RefCell<u64>>,
+    }
+    impl Layer for CountingLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, _ctx: &mut crate::core::layers::UpdateContext) {
+            *self.updates.borrow_mut() += 1;
+        }
+        fn close(&mut self) {}
+    }
+
+    struct Listener {
+        times_called: Rc<RefCell<u32>>,
+    }
+    impl EventListener<event::AppUpdate> for Listener {
+        fn invoke_event(&mut self, _event: &event::AppUpdate) -> EventEvaluateState {
+            *self.times_called.borrow_mut() += 1;
+            EventEvaluateState::Handled
+        }
+    }
+
+    #[test]
+    fn headless_tick_limit_test() {
+        let updates = Rc::new(RefCell::new(0));
+
+        let app = crate::App::begin_build()
+            .headless()
+            .with_tick_limit(10)
+            .add_layer(Box::new(CountingLayer {
+                updates: updates.clone(),
+            }))
+            .build()
+            .unwrap();
+
+        app.run().unwrap();
+
+        assert_eq!(*updates.borrow(), 10);
+    }
+    #[test]
+    fn headless_stop_condition_test() {
+        let number = Rc::new(RefCell::new(0));
+        let listener_number = number.clone();
+
+        let app = crate::App::begin_build()
+            .headless()
+            .with_stop_condition(|app| app.frame() >= 5)
+            .add_init_hook(move |app| {
+                app.event_system.add_listener(Box::new(Listener {
+                    times_called: listener_number,
+                }));
+                app.event_system
+                    .queue_event(EventInfo::queued(event::AppUpdate));
+                Ok(())
+            })
+            .build()
+            .unwrap();
+
+        app.run().unwrap();
+
+        assert_eq!(*number.borrow(), 1);
+    }
+
+    struct EmittingLayer {
+        frames: Rc<RefCell<Vec<u64>>>,
+    }
+    impl Layer for EmittingLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, ctx: &mut crate::core::layers::UpdateContext) {
+            assert!(ctx.elapsed >= ctx.delta);
+            self.frames.borrow_mut().push(ctx.frame);
+            ctx.event_system
+                .queue_event(EventInfo::queued(event::AppUpdate));
+        }
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn layer_update_context_test() {
+        let frames = Rc::new(RefCell::new(vec![]));
+        let number = Rc::new(RefCell::new(0));
+        let listener_number = number.clone();
+
+        let app = crate::App::begin_build()
+            .with_tick_limit(5)
+            .add_init_hook(move |app| {
+                app.event_system.add_listener(Box::new(Listener {
+                    times_called: listener_number,
+                }));
+                Ok(())
+            })
+            .add_layer(Box::new(EmittingLayer {
+                frames: frames.clone(),
+            }))
+            .build()
+            .unwrap();
+
+        app.run().unwrap();
+
+        assert_eq!(*frames.borrow(), vec![1, 2, 3, 4, 5]);
+        // events queued during a frame are handled at the start of the next one
+        assert_eq!(*number.borrow(), 4);
+    }
+}
@@ -12,10 +12,12 @@ struct Action {
 
 #[derive(Copy, Clone, Debug)]
 pub struct ActionId(usize);
+#[allow(dead_code)]
 #[derive(Copy, Clone, Debug)]
 pub struct BindingId(usize, ActionId);
 
 struct Binding {
+    #[allow(dead_code)]
     id: BindingId,
     key: keyboard::Keycode,
 }
@@ -101,15 +103,15 @@ impl InputSystem {
 impl InputSystemMarker for Arc<Mutex<InputSystem>> {
     fn register(&mut self, name: &'static str) -> ActionId {
         let mut lock = self.lock().expect("failed to aquire mutex lock");
-        InputSystem::register(&mut *lock, name)
+        InputSystem::register(&mut lock, name)
     }
     fn bind(&mut self, action_id: ActionId, key: keyboard::Keycode) -> BindingId {
         let mut lock = self.lock().expect("failed to aquire mutex lock");
-        InputSystem::bind(&mut *lock, action_id, key)
+        InputSystem::bind(&mut lock, action_id, key)
     }
     fn query(&self, action: ActionId) -> KeyState {
         let lock = self.lock().expect("failed to aquire mutex lock");
-        InputSystem::query(&*lock, action)
+        InputSystem::query(&lock, action)
     }
 }
 
@@ -117,7 +119,7 @@ impl Layer for Arc<Mutex<InputSystem>> {
     fn init(&mut self, app: &mut crate::App) {
         app.event_system.add_listener(Box::new(self.clone()));
     }
-    fn update(&mut self) {}
+    fn update(&mut self, _ctx: &mut crate::core::layers::UpdateContext) {}
     fn close(&mut self) {}
 }
 impl EventListener<event::KeyboardEvent> for Arc<Mutex<InputSystem>> {
@@ -1,3 +1,8 @@
 pub use flecs_ecs;
 pub mod core;
+pub mod headless;
 pub mod input;
+pub mod pacing;
+pub mod recording;
+pub mod timestep;
+pub mod window;
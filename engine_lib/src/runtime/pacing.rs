@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+/// The frame rate used while the window is unfocused, unless set otherwise
+pub const DEFAULT_UNFOCUSED_FPS: f64 = 30.0;
+
+/// How fast the app runs frames
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FrameLimit {
+    /// runs frames as fast as possible
+    Unlimited,
+    /// runs at most this many frames per second, the app sleeps in between, a rate that isn't
+    /// a positive number runs unlimited
+    Fps(f64),
+    /// presents with [`crate::renderer::PresentMode::Fifo`] and lets presentation pace the frames
+    Vsync,
+}
+
+impl FrameLimit {
+    fn fps(self) -> Option<f64> {
+        match self {
+            FrameLimit::Fps(fps) => Some(fps).filter(|&fps| is_valid_fps(fps)),
+            FrameLimit::Unlimited | FrameLimit::Vsync => None,
+        }
+    }
+}
+
+pub fn is_valid_fps(fps: f64) -> bool {
+    fps.is_finite() && fps > 0.0
+}
+
+/// Decides when the next frame should run
+pub struct FramePacer {
+    pub limit: FrameLimit,
+    /// the frame rate while the window is unfocused, `None` or a rate that isn't a positive
+    /// number keeps `limit`
+    pub unfocused_fps: Option<f64>,
+    next_frame: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new(limit: FrameLimit) -> Self {
+        FramePacer {
+            limit,
+            unfocused_fps: Some(DEFAULT_UNFOCUSED_FPS),
+            next_frame: None,
+        }
+    }
+    fn frame_interval(&self, focused: bool) -> Option<Duration> {
+        let fps = match focused {
+            true => self.limit.fps(),
+            false => self
+                .unfocused_fps
+                .filter(|&fps| is_valid_fps(fps))
+                .or(self.limit.fps()),
+        };
+        // a tiny rate overflows the interval, it is treated as unlimited too
+        fps.and_then(|fps| Duration::try_from_secs_f64(1.0 / fps).ok())
+    }
+    /// whether a frame is due at `now`
+    pub fn should_run(&self, now: Instant) -> bool {
+        self.next_frame.is_none_or(|next_frame| now >= next_frame)
+    }
+    /// schedules the frame after the one starting at `now`, returns when it is due or `None`
+    /// when it can run right away
+    ///
+    /// frames are scheduled from the previous deadline so the rate doesn't drift, unless the app
+    /// fell a whole frame behind
+    pub fn frame_started(&mut self, now: Instant, focused: bool) -> Option<Instant> {
+        self.next_frame = self
+            .frame_interval(focused)
+            .map(|interval| match self.next_frame {
+                Some(next_frame) if now.saturating_duration_since(next_frame) < interval => {
+                    next_frame + interval
+                }
+                _ => now + interval,
+            });
+        self.next_frame
+    }
+    /// when the next frame is due, `None` when it can run right away
+    pub fn next_frame(&self) -> Option<Instant> {
+        self.next_frame
+    }
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new(FrameLimit::Unlimited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_pacer_schedules_frames_test() {
+        let mut pacer = FramePacer::new(FrameLimit::Fps(10.0));
+        let start = Instant::now();
+
+        assert!(pacer.should_run(start));
+        let next = pacer.frame_started(start, true).unwrap();
+        assert_eq!(next, start + Duration::from_millis(100));
+        assert!(!pacer.should_run(start + Duration::from_millis(50)));
+
+        // a slightly late frame keeps the schedule, a frame that is too late restarts it
+        let late = next + Duration::from_millis(10);
+        assert_eq!(
+            pacer.frame_started(late, true),
+            Some(start + Duration::from_millis(200))
+        );
+        let very_late = start + Duration::from_millis(500);
+        assert_eq!(
+            pacer.frame_started(very_late, true),
+            Some(very_late + Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn frame_pacer_unfocused_test() {
+        let mut pacer = FramePacer::new(FrameLimit::Unlimited);
+        let start = Instant::now();
+
+        assert_eq!(pacer.frame_started(start, true), None);
+        assert!(pacer.should_run(start));
+        assert_eq!(
+            pacer.frame_started(start, false),
+            Some(start + Duration::from_secs_f64(1.0 / DEFAULT_UNFOCUSED_FPS))
+        );
+
+        pacer.unfocused_fps = None;
+        assert_eq!(pacer.frame_started(start, false), None);
+    }
+
+    #[test]
+    fn frame_pacer_invalid_fps_test() {
+        let start = Instant::now();
+        for fps in [0.0, -30.0, f64::NAN, 1e-320] {
+            let mut pacer = FramePacer::new(FrameLimit::Fps(fps));
+            pacer.unfocused_fps = Some(fps);
+            assert_eq!(pacer.frame_started(start, true), None);
+            assert_eq!(pacer.frame_started(start, false), None);
+        }
+
+        let mut pacer = FramePacer::new(FrameLimit::Fps(10.0));
+        pacer.unfocused_fps = Some(0.0);
+        assert_eq!(
+            pacer.frame_started(start, false),
+            Some(start + Duration::from_millis(100))
+        );
+    }
+}
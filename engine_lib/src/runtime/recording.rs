@@ -0,0 +1,425 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::error::EngineError;
+use crate::core::events::event::{
+    KeyboardEvent, MouseEvent, MouseMotion, MouseScroll, WindowClose, WindowFocus, WindowLoseFocus,
+    WindowResize,
+};
+use crate::core::events::keyboard::{KeyState, Keycode};
+use crate::core::events::mouse::MouseButton;
+use crate::core::events::{Event, EventInfo, EventSystem};
+
+/// The first line of a recording file
+const HEADER: &str = "# event recording v1";
+
+/// An input or window event that can be recorded and replayed
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordedEvent {
+    Keyboard(Keycode, KeyState),
+    Mouse(MouseButton, KeyState),
+    MouseMotion((f32, f32)),
+    MouseScroll(f32),
+    WindowFocus,
+    WindowLoseFocus,
+    WindowResize((u32, u32)),
+    WindowClose,
+}
+
+impl RecordedEvent {
+    /// `None` when the event isn't recorded, e.g. [`crate::core::events::event::AppUpdate`]
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if let Some(KeyboardEvent(code, state)) = event.downcast() {
+            Some(RecordedEvent::Keyboard(*code, *state))
+        } else if let Some(MouseEvent(button, state)) = event.downcast() {
+            Some(RecordedEvent::Mouse(*button, *state))
+        } else if let Some(MouseMotion(delta)) = event.downcast() {
+            Some(RecordedEvent::MouseMotion(*delta))
+        } else if let Some(MouseScroll(delta)) = event.downcast() {
+            Some(RecordedEvent::MouseScroll(*delta))
+        } else if event.is::<WindowFocus>() {
+            Some(RecordedEvent::WindowFocus)
+        } else if event.is::<WindowLoseFocus>() {
+            Some(RecordedEvent::WindowLoseFocus)
+        } else if let Some(WindowResize(size)) = event.downcast() {
+            Some(RecordedEvent::WindowResize(*size))
+        } else if event.is::<WindowClose>() {
+            Some(RecordedEvent::WindowClose)
+        } else {
+            None
+        }
+    }
+    /// queues the event, replayed events are always queued even if they were blocking
+    pub fn queue(self, event_system: &mut EventSystem) {
+        match self {
+            RecordedEvent::Keyboard(code, state) => {
+                event_system.queue_event(EventInfo::queued(KeyboardEvent(code, state)))
+            }
+            RecordedEvent::Mouse(button, state) => {
+                event_system.queue_event(EventInfo::queued(MouseEvent(button, state)))
+            }
+            RecordedEvent::MouseMotion(delta) => {
+                event_system.queue_event(EventInfo::queued(MouseMotion(delta)))
+            }
+            RecordedEvent::MouseScroll(delta) => {
+                event_system.queue_event(EventInfo::queued(MouseScroll(delta)))
+            }
+            RecordedEvent::WindowFocus => event_system.queue_event(EventInfo::queued(WindowFocus)),
+            RecordedEvent::WindowLoseFocus => {
+                event_system.queue_event(EventInfo::queued(WindowLoseFocus))
+            }
+            RecordedEvent::WindowResize(size) => {
+                event_system.queue_event(EventInfo::queued(WindowResize(size)))
+            }
+            RecordedEvent::WindowClose => event_system.queue_event(EventInfo::queued(WindowClose)),
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            RecordedEvent::Keyboard(code, state) => {
+                format!("key {:?} {}", code, key_state_name(*state))
+            }
+            RecordedEvent::Mouse(button, state) => {
+                format!(
+                    "mouse {} {}",
+                    mouse_button_name(button),
+                    key_state_name(*state)
+                )
+            }
+            RecordedEvent::MouseMotion((x, y)) => format!("motion {} {}", x, y),
+            RecordedEvent::MouseScroll(delta) => format!("scroll {}", delta),
+            RecordedEvent::WindowFocus => "focus".to_string(),
+            RecordedEvent::WindowLoseFocus => "lose_focus".to_string(),
+            RecordedEvent::WindowResize((width, height)) => format!("resize {} {}", width, height),
+            RecordedEvent::WindowClose => "close".to_string(),
+        }
+    }
+    fn parse(words: &[&str]) -> Result<Self, String> {
+        fn number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+            word.parse()
+                .map_err(|_| format!("invalid number {:?}", word))
+        }
+        Ok(match words {
+            ["key", code, state] => {
+                RecordedEvent::Keyboard(parse_keycode(code)?, parse_key_state(state)?)
+            }
+            ["mouse", button, state] => {
+                RecordedEvent::Mouse(parse_mouse_button(button)?, parse_key_state(state)?)
+            }
+            ["motion", x, y] => RecordedEvent::MouseMotion((number(x)?, number(y)?)),
+            ["scroll", delta] => RecordedEvent::MouseScroll(number(delta)?),
+            ["focus"] => RecordedEvent::WindowFocus,
+            ["lose_focus"] => RecordedEvent::WindowLoseFocus,
+            ["resize", width, height] => {
+                RecordedEvent::WindowResize((number(width)?, number(height)?))
+            }
+            ["close"] => RecordedEvent::WindowClose,
+            _ => return Err(format!("unknown event {:?}", words.join(" "))),
+        })
+    }
+}
+
+fn key_state_name(state: KeyState) -> &'static str {
+    match state {
+        KeyState::Down => "down",
+        KeyState::Repeat => "repeat",
+        KeyState::Up => "up",
+    }
+}
+fn parse_key_state(name: &str) -> Result<KeyState, String> {
+    match name {
+        "down" => Ok(KeyState::Down),
+        "repeat" => Ok(KeyState::Repeat),
+        "up" => Ok(KeyState::Up),
+        _ => Err(format!("unknown key state {:?}", name)),
+    }
+}
+fn mouse_button_name(button: &MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+        MouseButton::Forward => "forward",
+        MouseButton::Back => "back",
+    }
+}
+fn parse_mouse_button(name: &str) -> Result<MouseButton, String> {
+    match name {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        "forward" => Ok(MouseButton::Forward),
+        "back" => Ok(MouseButton::Back),
+        _ => Err(format!("unknown mouse button {:?}", name)),
+    }
+}
+
+macro_rules! keycodes {
+    ($($name:ident)*) => {
+        &[$(Keycode::$name),*]
+    };
+}
+/// every key code, written by their `Debug` name
+const KEYCODES: &[Keycode] = keycodes!(
+    Backquote Backslash BracketLeft BracketRight Comma Digit0 Digit1 Digit2 Digit3 Digit4 Digit5
+    Digit6 Digit7 Digit8 Digit9 Equal IntlBackslash IntlRo IntlYen KeyA KeyB KeyC KeyD KeyE KeyF
+    KeyG KeyH KeyI KeyJ KeyK KeyL KeyM KeyN KeyO KeyP KeyQ KeyR KeyS KeyT KeyU KeyV KeyW KeyX KeyY
+    KeyZ Minus Period Quote Semicolon Slash AltLeft AltRight Backspace CapsLock ContextMenu
+    ControlLeft ControlRight Enter SuperLeft SuperRight ShiftLeft ShiftRight Space Tab Convert
+    KanaMode Lang1 Lang2 Lang3 Lang4 Lang5 NonConvert Delete End Help Home Insert PageDown PageUp
+    ArrowDown ArrowLeft ArrowRight ArrowUp NumLock Numpad0 Numpad1 Numpad2 Numpad3 Numpad4 Numpad5
+    Numpad6 Numpad7 Numpad8 Numpad9 NumpadAdd NumpadBackspace NumpadClear NumpadClearEntry
+    NumpadComma NumpadDecimal NumpadDivide NumpadEnter NumpadEqual NumpadHash NumpadMemoryAdd
+    NumpadMemoryClear NumpadMemoryRecall NumpadMemoryStore NumpadMemorySubtract NumpadMultiply
+    NumpadParenLeft NumpadParenRight NumpadStar NumpadSubtract Escape Fn FnLock PrintScreen
+    ScrollLock Pause BrowserBack BrowserFavorites BrowserForward BrowserHome BrowserRefresh
+    BrowserSearch BrowserStop Eject LaunchApp1 LaunchApp2 LaunchMail MediaPlayPause MediaSelect
+    MediaStop MediaTrackNext MediaTrackPrevious Power Sleep AudioVolumeDown AudioVolumeMute
+    AudioVolumeUp WakeUp Meta Hyper Turbo Abort Resume Suspend Again Copy Cut Find Open Paste Props
+    Select Undo Hiragana Katakana F1 F2 F3 F4 F5 F6 F7 F8 F9 F10 F11 F12 F13 F14 F15 F16 F17 F18
+    F19 F20 F21 F22 F23 F24 F25 F26 F27 F28 F29 F30 F31 F32 F33 F34 F35
+);
+fn parse_keycode(name: &str) -> Result<Keycode, String> {
+    KEYCODES
+        .iter()
+        .find(|code| format!("{:?}", code) == name)
+        .copied()
+        .ok_or_else(|| format!("unknown key code {:?}", name))
+}
+
+/// Events stamped with the frame they were dispatched in, one per line in a file, e.g.
+/// `12 key KeyW down`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    /// the frame and the event, in the order they were dispatched
+    pub events: Vec<(u64, RecordedEvent)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Recording { events: vec![] }
+    }
+    pub fn push(&mut self, frame: u64, event: RecordedEvent) {
+        self.events.push((frame, event));
+    }
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        for (frame, event) in &self.events {
+            text.push_str(&format!("{} {}\n", frame, event.to_line()));
+        }
+        text
+    }
+    /// empty lines and lines starting with `#` are skipped
+    pub fn parse(text: &str) -> Result<Self, EngineError> {
+        let mut recording = Recording::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message| EngineError::InvalidRecording {
+                line: index + 1,
+                message,
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let frame = words[0]
+                .parse()
+                .map_err(|_| invalid(format!("invalid frame {:?}", words[0])))?;
+            let event = RecordedEvent::parse(&words[1..]).map_err(invalid)?;
+            recording.push(frame, event);
+        }
+        Ok(recording)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        std::fs::write(path, self.to_text()).map_err(EngineError::Recording)
+    }
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(EngineError::Recording)?)
+    }
+}
+
+/// Records the input and window events the app gets from the OS, see
+/// [`crate::core::initialization::AppBuilder::with_event_recording`]
+///
+/// events queued by layers or listeners aren't recorded, they are queued again on replay
+pub struct EventRecorder {
+    pub recording: Recording,
+    /// where the recording is saved when the app closes
+    pub path: Option<PathBuf>,
+}
+
+impl EventRecorder {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        EventRecorder {
+            recording: Recording::new(),
+            path,
+        }
+    }
+    /// records `event` for `frame`, does nothing when it isn't a [`RecordedEvent`]
+    pub fn record(&mut self, frame: u64, event: &Event) {
+        if let Some(recorded) = RecordedEvent::from_event(event) {
+            self.recording.push(frame, recorded);
+        }
+    }
+    /// saves the recording to [`EventRecorder::path`], if any
+    pub fn save(&self) -> Result<(), EngineError> {
+        match &self.path {
+            Some(path) => self.recording.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Feeds a recording back to the app at the recorded frames, see
+/// [`crate::core::initialization::AppBuilder::with_replay`]
+pub struct EventReplay {
+    recording: Recording,
+    next: usize,
+}
+
+impl EventReplay {
+    pub fn new(recording: Recording) -> Self {
+        EventReplay { recording, next: 0 }
+    }
+    /// queues the events recorded for `frame`, events of earlier frames that weren't queued yet
+    /// are queued too
+    pub fn queue_frame(&mut self, frame: u64, event_system: &mut EventSystem) {
+        while let Some((event_frame, event)) = self.recording.events.get(self.next) {
+            if *event_frame > frame {
+                break;
+            }
+            event.clone().queue(event_system);
+            self.next += 1;
+        }
+    }
+    /// true once every event was queued
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::core::events::EventEvaluateState;
+    use crate::core::layers::{Layer, UpdateContext};
+
+    #[test]
+    fn recording_text_test() {
+        let mut recording = Recording::new();
+        recording.push(1, RecordedEvent::Keyboard(Keycode::KeyW, KeyState::Down));
+        recording.push(1, RecordedEvent::Mouse(MouseButton::Right, KeyState::Up));
+        recording.push(2, RecordedEvent::MouseMotion((0.1, -2.5)));
+        recording.push(3, RecordedEvent::MouseScroll(1.0));
+        recording.push(3, RecordedEvent::WindowResize((800, 600)));
+        recording.push(4, RecordedEvent::WindowLoseFocus);
+        recording.push(5, RecordedEvent::WindowClose);
+
+        let text = recording.to_text();
+        assert!(text.contains("1 key KeyW down\n"));
+        assert_eq!(Recording::parse(&text).unwrap(), recording);
+
+        assert!(matches!(
+            Recording::parse("1 key KeyW down\n2 key NotAKey down"),
+            Err(EngineError::InvalidRecording { line: 2, .. })
+        ));
+    }
+
+    /// presses a key on some frames
+    struct PressingLayer;
+    impl Layer for PressingLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, ctx: &mut UpdateContext) {
+            if ctx.frame.is_multiple_of(3) {
+                ctx.event_system
+                    .queue_event(EventInfo::queued(KeyboardEvent(
+                        Keycode::Space,
+                        KeyState::Down,
+                    )));
+            }
+        }
+        fn close(&mut self) {}
+    }
+
+    /// keeps the frames keys were pressed in
+    struct PressedLayer {
+        frame: u64,
+        pressed: Rc<RefCell<Vec<(u64, Keycode)>>>,
+    }
+    impl Layer for PressedLayer {
+        fn init(&mut self, _app: &mut crate::App) {}
+        fn update(&mut self, ctx: &mut UpdateContext) {
+            self.frame = ctx.frame;
+        }
+        fn on_event(&mut self, event: &Event) -> EventEvaluateState {
+            if let Some(KeyboardEvent(code, _)) = event.downcast() {
+                // events are dispatched before the layers are updated
+                self.pressed.borrow_mut().push((self.frame + 1, *code));
+            }
+            EventEvaluateState::Unhandled
+        }
+        fn close(&mut self) {}
+    }
+    fn pressing_app(
+        builder: crate::core::initialization::AppBuilder,
+        pressed: &Rc<RefCell<Vec<(u64, Keycode)>>>,
+    ) -> crate::App {
+        builder
+            .headless()
+            .add_layer(Box::new(PressingLayer))
+            .add_layer(Box::new(PressedLayer {
+                frame: 0,
+                pressed: pressed.clone(),
+            }))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn record_and_replay_test() {
+        let path = std::env::temp_dir().join(format!("recording-{}.txt", std::process::id()));
+
+        let recorded = Rc::new(RefCell::new(vec![]));
+        let builder = crate::App::begin_build().with_event_recording(&path);
+        let mut app = pressing_app(builder, &recorded);
+        for frame in 1..=10 {
+            if frame % 4 == 2 {
+                app.send_event(EventInfo::queued(KeyboardEvent(
+                    Keycode::KeyW,
+                    KeyState::Down,
+                )));
+            }
+            app.tick();
+        }
+
+        // only the events from the OS are recorded, not the ones PressingLayer queued
+        app.close();
+        assert_eq!(
+            Recording::load(&path).unwrap().events,
+            vec![
+                (2, RecordedEvent::Keyboard(Keycode::KeyW, KeyState::Down)),
+                (6, RecordedEvent::Keyboard(Keycode::KeyW, KeyState::Down)),
+                (10, RecordedEvent::Keyboard(Keycode::KeyW, KeyState::Down)),
+            ]
+        );
+
+        let replayed = Rc::new(RefCell::new(vec![]));
+        let builder = crate::App::begin_build().with_replay(&path);
+        let mut app = pressing_app(builder, &replayed);
+        for _ in 1..=10 {
+            app.tick();
+        }
+        assert!(app.replay_finished());
+        _ = std::fs::remove_file(&path);
+
+        let count = |pressed: &[(u64, Keycode)], key| {
+            pressed.iter().filter(|(_, code)| *code == key).count()
+        };
+        assert_eq!(count(&recorded.borrow(), Keycode::Space), 3);
+        assert_eq!(count(&recorded.borrow(), Keycode::KeyW), 3);
+        assert_eq!(*replayed.borrow(), *recorded.borrow());
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+pub const DEFAULT_FIXED_RATE: f64 = 60.0;
+pub const DEFAULT_MAX_FIXED_STEPS: u32 = 5;
+
+/// Turns the variable time between frames into a whole number of fixed steps, the time left
+/// over is carried to the next frame
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// `rate` is the number of steps per second, a rate that isn't a positive number falls back
+    /// to [`DEFAULT_FIXED_RATE`]
+    pub fn new(rate: f64) -> Self {
+        let rate = match Self::is_valid_rate(rate) {
+            true => rate,
+            false => DEFAULT_FIXED_RATE,
+        };
+        FixedTimestep {
+            // a step of zero would never stop accumulating
+            step: Duration::from_secs_f64(1.0 / rate).max(Duration::from_nanos(1)),
+            max_steps: DEFAULT_MAX_FIXED_STEPS,
+            accumulator: Duration::ZERO,
+        }
+    }
+    /// limits the steps run in a single frame so a slow frame doesn't snowball, the time that
+    /// couldn't be simulated is dropped
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+    pub fn is_valid_rate(rate: f64) -> bool {
+        rate.is_finite() && rate > 0.0
+    }
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+    /// adds `delta` to the accumulator and returns how many steps to run
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+            if steps == self.max_steps {
+                // keeps the phase of the steps but drops the time that couldn't be simulated
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+        }
+        steps
+    }
+    /// how far the current time is between the last step and the next, from 0 to 1, used to
+    /// interpolate between the last two simulated states
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(DEFAULT_FIXED_RATE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_timestep_accumulates_test() {
+        let mut timestep = FixedTimestep::new(10.0);
+
+        assert_eq!(timestep.advance(Duration::from_millis(50)), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(timestep.advance(Duration::from_millis(175)), 2);
+        assert!((timestep.alpha() - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fixed_timestep_max_steps_test() {
+        let mut timestep = FixedTimestep::new(10.0).with_max_steps(3);
+
+        assert_eq!(timestep.advance(Duration::from_millis(1050)), 3);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(timestep.advance(Duration::from_millis(50)), 1);
+    }
+
+    #[test]
+    fn fixed_timestep_invalid_rate_test() {
+        let default_step = FixedTimestep::default().step();
+        for rate in [0.0, -10.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(FixedTimestep::new(rate).step(), default_step);
+        }
+
+        let mut timestep = FixedTimestep::new(1e12).with_max_steps(2);
+        assert_eq!(timestep.step(), Duration::from_nanos(1));
+        assert_eq!(timestep.advance(Duration::from_millis(1)), 2);
+    }
+}
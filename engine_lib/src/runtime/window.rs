@@ -0,0 +1,190 @@
+use std::sync::{Arc, Mutex};
+
+use crate::core::initialization::{CursorGrab, FullscreenMode};
+
+/// A change to the window requested through a [`WindowHandle`], applied by the app before the
+/// next frame
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum WindowCommand {
+    Title(String),
+    Fullscreen(Option<FullscreenMode>),
+    CursorGrab(CursorGrab),
+    CursorVisible(bool),
+}
+
+struct WindowState {
+    size: (u32, u32),
+    scale_factor: f64,
+    fullscreen: Option<FullscreenMode>,
+    cursor_grab: CursorGrab,
+    cursor_visible: bool,
+    close_requested: bool,
+    commands: Vec<WindowCommand>,
+}
+
+/// Controls the app's window from layers, get one with [`crate::App::window_handle`]
+///
+/// changes are queued and applied before the next frame, the window may not exist yet when a
+/// layer is initialized
+#[derive(Clone)]
+pub struct WindowHandle {
+    state: Arc<Mutex<WindowState>>,
+}
+
+impl WindowHandle {
+    pub(crate) fn new(
+        size: (u32, u32),
+        fullscreen: Option<FullscreenMode>,
+        cursor_grab: CursorGrab,
+        cursor_visible: bool,
+    ) -> Self {
+        WindowHandle {
+            state: Arc::new(Mutex::new(WindowState {
+                size,
+                scale_factor: 1.0,
+                fullscreen,
+                cursor_grab,
+                cursor_visible,
+                close_requested: false,
+                commands: vec![],
+            })),
+        }
+    }
+    fn push(&self, command: WindowCommand) {
+        self.state.lock().unwrap().commands.push(command);
+    }
+
+    pub fn set_title(&self, title: impl Into<String>) {
+        self.push(WindowCommand::Title(title.into()));
+    }
+    /// `None` leaves fullscreen
+    pub fn set_fullscreen(&self, fullscreen: Option<FullscreenMode>) {
+        let mut state = self.state.lock().unwrap();
+        state.fullscreen = fullscreen;
+        state.commands.push(WindowCommand::Fullscreen(fullscreen));
+    }
+    /// switches between windowed and borderless fullscreen
+    pub fn toggle_fullscreen(&self) {
+        let fullscreen = match self.fullscreen() {
+            Some(_) => None,
+            None => Some(FullscreenMode::Borderless),
+        };
+        self.set_fullscreen(fullscreen);
+    }
+    pub fn fullscreen(&self) -> Option<FullscreenMode> {
+        self.state.lock().unwrap().fullscreen
+    }
+    pub fn set_cursor_grab(&self, grab: CursorGrab) {
+        let mut state = self.state.lock().unwrap();
+        state.cursor_grab = grab;
+        state.commands.push(WindowCommand::CursorGrab(grab));
+    }
+    pub fn set_cursor_visible(&self, visible: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.cursor_visible = visible;
+        state.commands.push(WindowCommand::CursorVisible(visible));
+    }
+    /// locks and hides the cursor for mouselook, the movement is still reported through
+    /// [`crate::core::events::event::MouseMotion`] events
+    pub fn lock_cursor(&self, locked: bool) {
+        self.set_cursor_grab(match locked {
+            true => CursorGrab::Locked,
+            false => CursorGrab::None,
+        });
+        self.set_cursor_visible(!locked);
+    }
+    pub fn cursor_grab(&self) -> CursorGrab {
+        self.state.lock().unwrap().cursor_grab
+    }
+    pub fn cursor_visible(&self) -> bool {
+        self.state.lock().unwrap().cursor_visible
+    }
+    /// closes the window and stops the app before the next frame
+    pub fn request_close(&self) {
+        self.state.lock().unwrap().close_requested = true;
+    }
+    pub fn close_requested(&self) -> bool {
+        self.state.lock().unwrap().close_requested
+    }
+    /// the inner size of the window in physical pixels
+    pub fn size(&self) -> (u32, u32) {
+        self.state.lock().unwrap().size
+    }
+    /// the ratio of physical to logical pixels
+    pub fn scale_factor(&self) -> f64 {
+        self.state.lock().unwrap().scale_factor
+    }
+
+    pub(crate) fn set_size(&self, size: (u32, u32)) {
+        self.state.lock().unwrap().size = size;
+    }
+    pub(crate) fn set_scale_factor(&self, scale_factor: f64) {
+        self.state.lock().unwrap().scale_factor = scale_factor;
+    }
+    pub(crate) fn take_commands(&self) -> Vec<WindowCommand> {
+        std::mem::take(&mut self.state.lock().unwrap().commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::layers::Layer;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn window_handle_commands_test() {
+        let handle = WindowHandle::new((640, 480), None, CursorGrab::None, true);
+
+        handle.toggle_fullscreen();
+        handle.lock_cursor(true);
+        handle.set_title("title");
+
+        assert_eq!(handle.fullscreen(), Some(FullscreenMode::Borderless));
+        assert_eq!(handle.cursor_grab(), CursorGrab::Locked);
+        assert_eq!(
+            handle.take_commands(),
+            vec![
+                WindowCommand::Fullscreen(Some(FullscreenMode::Borderless)),
+                WindowCommand::CursorGrab(CursorGrab::Locked),
+                WindowCommand::CursorVisible(false),
+                WindowCommand::Title("title".to_string()),
+            ]
+        );
+        assert!(handle.take_commands().is_empty());
+    }
+
+    struct ClosingLayer {
+        window: Option<WindowHandle>,
+        updates: Rc<Cell<u32>>,
+    }
+    impl Layer for ClosingLayer {
+        fn init(&mut self, app: &mut crate::App) {
+            self.window = Some(app.window_handle());
+        }
+        fn update(&mut self, _ctx: &mut crate::core::layers::UpdateContext) {
+            self.updates.set(self.updates.get() + 1);
+            if self.updates.get() == 3 {
+                self.window.as_ref().unwrap().request_close();
+            }
+        }
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn request_close_stops_headless_app_test() {
+        let updates = Rc::new(Cell::new(0));
+        let mut app = crate::App::begin_build()
+            .with_tick_limit(100)
+            .build()
+            .unwrap();
+        app.add_layer(Box::new(ClosingLayer {
+            window: None,
+            updates: updates.clone(),
+        }));
+
+        app.run().unwrap();
+        assert_eq!(updates.get(), 3);
+    }
+}
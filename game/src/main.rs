@@ -1,5 +1,3 @@
-use engine_lib;
-
 fn main() {
     let mut app = engine_lib::App::begin_build()
         .with_input_system()
@@ -26,7 +24,7 @@ struct ECSLayer;
 
 impl engine_lib::core::layers::Layer for ECSLayer {
     fn init(&mut self, _app: &mut engine_lib::App) {}
-    fn update(&mut self) {}
+    fn update(&mut self, _ctx: &mut engine_lib::core::layers::UpdateContext) {}
     fn close(&mut self) {}
 }
 